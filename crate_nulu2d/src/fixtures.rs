@@ -0,0 +1,18 @@
+// Shapes shared by the tests of several modules
+use crate::Point;
+use crate::Polygon;
+
+// Axis-aligned, counter-clockwise, with its bottom left corner at (x, y)
+pub(crate) fn square(x: f64, y: f64, side: f64) -> Polygon {
+    return rectangle(x, y, x + side, y + side);
+}
+
+// Axis-aligned and counter-clockwise
+pub(crate) fn rectangle(left: f64, bottom: f64, right: f64, top: f64) -> Polygon {
+    Polygon::new(&vec![
+        Point::new(left, bottom),
+        Point::new(right, bottom),
+        Point::new(right, top),
+        Point::new(left, top),
+    ])
+}
@@ -0,0 +1,173 @@
+use crate::Point;
+use crate::Vector;
+
+use similar::Similar;
+use similar_derive::Similar;
+
+/// Axis-aligned bounding box, from its bottom left to its top right corner.
+#[derive(Copy, Clone, Debug, PartialEq, Similar)]
+pub struct Aabb {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Aabb {
+    // Constructors
+    pub fn new(min: Point, max: Point) -> Aabb {
+        Aabb { min, max }
+    }
+
+    // Smallest box containing all the points, in a single pass
+    pub fn from_points(points: &[Point]) -> Aabb {
+        assert!(!points.is_empty(), "An AABB needs at least one point");
+        let first = Aabb::new(points[0], points[0]);
+        return points[1..].iter().fold(first, |aabb, p| Aabb {
+            min: Point::new(f64::min(aabb.min.x, p.x), f64::min(aabb.min.y, p.y)),
+            max: Point::new(f64::max(aabb.max.x, p.x), f64::max(aabb.max.y, p.y)),
+        });
+    }
+
+    // Accessors
+    pub fn width(&self) -> f64 {
+        return self.max.x - self.min.x;
+    }
+
+    pub fn height(&self) -> f64 {
+        return self.max.y - self.min.y;
+    }
+
+    pub fn center(&self) -> Point {
+        return (self.min + self.max) / 2.0;
+    }
+
+    pub fn area(&self) -> f64 {
+        return self.width() * self.height();
+    }
+
+    // Used as the cost of a box by bounding volume hierarchies
+    pub fn perimeter(&self) -> f64 {
+        return 2.0 * (self.width() + self.height());
+    }
+
+    // Touching boxes overlap
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        return self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y;
+    }
+
+    pub fn contains(&self, other: &Aabb) -> bool {
+        return self.min.x <= other.min.x
+            && self.min.y <= other.min.y
+            && other.max.x <= self.max.x
+            && other.max.y <= self.max.y;
+    }
+
+    pub fn contains_point(&self, point: Point) -> bool {
+        return self.min.x <= point.x
+            && point.x <= self.max.x
+            && self.min.y <= point.y
+            && point.y <= self.max.y;
+    }
+
+    // Smallest box containing both
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        return Aabb::from_points(&[self.min, self.max, other.min, other.max]);
+    }
+
+    // Returns `None` if the boxes don't overlap
+    pub fn intersection(&self, other: &Aabb) -> Option<Aabb> {
+        if !self.overlaps(other) {
+            return None;
+        }
+        return Some(Aabb {
+            min: Point::new(
+                f64::max(self.min.x, other.min.x),
+                f64::max(self.min.y, other.min.y),
+            ),
+            max: Point::new(
+                f64::min(self.max.x, other.max.x),
+                f64::min(self.max.y, other.max.y),
+            ),
+        });
+    }
+
+    // Grown by `margin` on every side
+    pub fn expanded(&self, margin: f64) -> Aabb {
+        let margin = Vector::new(margin, margin);
+        return Aabb {
+            min: self.min - margin,
+            max: self.max + margin,
+        };
+    }
+
+    // Covers the box all along its way while moving by `displacement`
+    pub fn swept(&self, displacement: Vector) -> Aabb {
+        return self.union(&self.moved(displacement));
+    }
+
+    pub fn moved(&self, offset: Vector) -> Aabb {
+        return Aabb {
+            min: self.min + offset,
+            max: self.max + offset,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar::assert_similar;
+
+    fn unit_at(x: f64, y: f64) -> Aabb {
+        Aabb::new(Point::new(x, y), Point::new(x + 1.0, y + 1.0))
+    }
+
+    #[test]
+    fn from_points() {
+        let points = [
+            Point::new(1.0, -2.0),
+            Point::new(-3.0, 0.5),
+            Point::new(0.0, 4.0),
+        ];
+        let aabb = Aabb::from_points(&points);
+        assert_similar!(aabb.min, Point::new(-3.0, -2.0));
+        assert_similar!(aabb.max, Point::new(1.0, 4.0));
+        assert_similar!(aabb.width(), 4.0);
+        assert_similar!(aabb.height(), 6.0);
+        assert_similar!(aabb.center(), Point::new(-1.0, 1.0));
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        let a = unit_at(0.0, 0.0);
+        let b = unit_at(0.5, 0.5);
+        assert_similar!(a.union(&b), Aabb::new(Point::zero(), Point::new(1.5, 1.5)));
+        assert_similar!(
+            a.intersection(&b),
+            Some(Aabb::new(Point::new(0.5, 0.5), Point::new(1.0, 1.0)))
+        );
+        assert_eq!(a.intersection(&unit_at(2.0, 0.0)), None);
+    }
+
+    #[test]
+    fn overlaps_and_contains() {
+        let a = unit_at(0.0, 0.0);
+        assert!(a.overlaps(&unit_at(1.0, 0.0)));
+        assert!(!a.overlaps(&unit_at(1.1, 0.0)));
+        assert!(a.expanded(0.5).contains(&unit_at(0.25, -0.25)));
+        assert!(!a.contains(&unit_at(0.25, -0.25)));
+        assert!(a.contains_point(Point::new(1.0, 0.5)));
+        assert!(!a.contains_point(Point::new(1.0, 1.5)));
+    }
+
+    #[test]
+    fn swept() {
+        let swept = unit_at(0.0, 0.0).swept(Vector::new(-2.0, 3.0));
+        assert_similar!(
+            swept,
+            Aabb::new(Point::new(-2.0, 0.0), Point::new(1.0, 4.0))
+        );
+    }
+}
@@ -0,0 +1,535 @@
+//! Boolean operations on polygons with holes, by edge classification: the
+//! edges of both operands are split wherever they cross or overlap, each
+//! piece is kept or dropped depending on which side of the other operand it
+//! lies, and the kept pieces are chained back into rings.
+//! Regions are described by rings whose interior is on their left, so
+//! outlines are counter-clockwise and holes clockwise.
+
+use crate::geometry::collision::parametric_intersection;
+use crate::geometry::polygon::signed_area;
+use crate::Point;
+use crate::Polygon;
+use crate::Segment;
+use crate::Vector;
+use std::collections::HashMap;
+use std::f64::consts;
+
+// Points closer than this are merged, and edges closer than this overlap
+const EPSILON: f64 = 1e-9;
+
+/// A polygon with holes, as returned by boolean operations. The outline is
+/// counter-clockwise and the holes clockwise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PolygonWithHoles {
+    pub outline: Polygon,
+    pub holes: Vec<Polygon>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum BooleanOp {
+    Union,
+    Intersection,
+    Difference,
+    Xor,
+}
+
+// Where a piece of edge lies relative to the other operand
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Location {
+    Inside,
+    Outside,
+    // On an edge of the other operand going the same way
+    SameBoundary,
+    // On an edge of the other operand going the opposite way
+    OppositeBoundary,
+}
+
+impl From<Polygon> for PolygonWithHoles {
+    fn from(polygon: Polygon) -> PolygonWithHoles {
+        PolygonWithHoles::new(polygon, vec![])
+    }
+}
+
+impl PolygonWithHoles {
+    // Sorts the outline counter-clockwise and the holes clockwise
+    pub fn new(outline: Polygon, holes: Vec<Polygon>) -> PolygonWithHoles {
+        let oriented = |polygon: Polygon, counter_clockwise: bool| {
+            let mut vertices = polygon.vertices;
+            if (signed_area(&vertices) > 0.0) != counter_clockwise {
+                vertices.reverse();
+            }
+            return Polygon { vertices };
+        };
+        PolygonWithHoles {
+            outline: oriented(outline, true),
+            holes: holes.into_iter().map(|h| oriented(h, false)).collect(),
+        }
+    }
+
+    pub fn area(&self) -> f64 {
+        return self.outline.area() - self.holes.iter().map(|h| h.area()).sum::<f64>();
+    }
+
+    pub fn contains_point(&self, point: Point) -> bool {
+        return ring_set_contains(&self.rings(), point);
+    }
+
+    pub fn union(&self, other: &PolygonWithHoles) -> Vec<PolygonWithHoles> {
+        return boolean(&self.rings(), &other.rings(), BooleanOp::Union);
+    }
+
+    pub fn intersection(&self, other: &PolygonWithHoles) -> Vec<PolygonWithHoles> {
+        return boolean(&self.rings(), &other.rings(), BooleanOp::Intersection);
+    }
+
+    pub fn difference(&self, other: &PolygonWithHoles) -> Vec<PolygonWithHoles> {
+        return boolean(&self.rings(), &other.rings(), BooleanOp::Difference);
+    }
+
+    pub fn xor(&self, other: &PolygonWithHoles) -> Vec<PolygonWithHoles> {
+        return boolean(&self.rings(), &other.rings(), BooleanOp::Xor);
+    }
+
+    pub(crate) fn rings(&self) -> Vec<Vec<Point>> {
+        return std::iter::once(&self.outline)
+            .chain(self.holes.iter())
+            .map(|polygon| polygon.vertices.clone())
+            .collect();
+    }
+}
+
+// The counter-clockwise ring of a simple polygon
+pub(crate) fn outline(polygon: &Polygon) -> Vec<Vec<Point>> {
+    return PolygonWithHoles::from(polygon.clone()).rings();
+}
+
+pub(crate) fn boolean(a: &[Vec<Point>], b: &[Vec<Point>], op: BooleanOp) -> Vec<PolygonWithHoles> {
+    if op == BooleanOp::Xor {
+        let mut result = boolean(a, b, BooleanOp::Difference);
+        result.extend(boolean(b, a, BooleanOp::Difference));
+        return result;
+    }
+
+    let mut vertices = Vec::new();
+    let edges_a = split_edges(a, b, &mut vertices);
+    let edges_b = split_edges(b, a, &mut vertices);
+
+    // Shared edges are only taken from `a`, so they aren't added twice
+    let mut kept = Vec::new();
+    for (p, q) in edges_a.iter().cloned() {
+        let keep = matches!(
+            (locate((p, q), &edges_b, &vertices), op),
+            (Location::Outside, BooleanOp::Union)
+                | (Location::SameBoundary, BooleanOp::Union)
+                | (Location::Inside, BooleanOp::Intersection)
+                | (Location::SameBoundary, BooleanOp::Intersection)
+                | (Location::Outside, BooleanOp::Difference)
+                | (Location::OppositeBoundary, BooleanOp::Difference)
+        );
+        if keep {
+            kept.push((p, q));
+        }
+    }
+    for (p, q) in edges_b.iter().cloned() {
+        match (locate((p, q), &edges_a, &vertices), op) {
+            (Location::Outside, BooleanOp::Union) => kept.push((p, q)),
+            (Location::Inside, BooleanOp::Intersection) => kept.push((p, q)),
+            // Holes carved into `a`, so they go the other way
+            (Location::Inside, BooleanOp::Difference) => kept.push((q, p)),
+            _ => {}
+        }
+    }
+
+    return assemble(chain(&vertices, &kept));
+}
+
+// Index of the vertex at `point`, adding it if no vertex is that close
+fn snap(vertices: &mut Vec<Point>, point: Point) -> usize {
+    match vertices.iter().position(|v| v.distance(point) < EPSILON) {
+        Some(index) => return index,
+        None => {
+            vertices.push(point);
+            return vertices.len() - 1;
+        }
+    }
+}
+
+fn ring_segments(ring: &[Point]) -> impl Iterator<Item = Segment> + '_ {
+    let len = ring.len();
+    return (0..len).map(move |i| Segment::from_points(ring[i], ring[(i + 1) % len]));
+}
+
+// Splits the edges of `rings` wherever they cross or touch the edges of
+// `others`, as pairs of vertex indices
+fn split_edges(
+    rings: &[Vec<Point>],
+    others: &[Vec<Point>],
+    vertices: &mut Vec<Point>,
+) -> Vec<(usize, usize)> {
+    let mut edges = Vec::new();
+    for ring in rings {
+        for segment in ring_segments(ring) {
+            let direction = segment.direction();
+            if direction.norm() < EPSILON {
+                continue;
+            }
+            let mut cuts = vec![0.0, 1.0];
+            for other in others.iter().flat_map(|o| ring_segments(o)) {
+                let within = |t: f64| (-EPSILON..=1.0 + EPSILON).contains(&t);
+                if let Some((t, s)) = parametric_intersection(segment, other) {
+                    if within(t) && within(s) {
+                        cuts.push(t);
+                    }
+                }
+                // Parallel overlaps, and vertices lying on the edge
+                for point in [other.a, other.b].iter() {
+                    if segment.closest_point(*point).distance(*point) < EPSILON {
+                        cuts.push((*point - segment.a) * direction / (direction * direction));
+                    }
+                }
+            }
+            cuts.sort_by(|t1, t2| t1.partial_cmp(t2).unwrap());
+
+            let mut indices: Vec<usize> = cuts
+                .into_iter()
+                .map(|t| snap(vertices, segment.a + direction * t.clamp(0.0, 1.0)))
+                .collect();
+            indices.dedup();
+            for pair in indices.windows(2) {
+                edges.push((pair[0], pair[1]));
+            }
+        }
+    }
+    return edges;
+}
+
+// Locates a piece of edge against the split edges of the other operand.
+// Both were snapped to the same vertices, so a piece lying on the boundary
+// of the other operand is one of its edges, and nothing is compared against
+// the original (unsnapped) rings.
+fn locate(edge: (usize, usize), others: &[(usize, usize)], vertices: &[Point]) -> Location {
+    let (p, q) = edge;
+    if others.contains(&(p, q)) {
+        return Location::SameBoundary;
+    }
+    if others.contains(&(q, p)) {
+        return Location::OppositeBoundary;
+    }
+    let segments = others
+        .iter()
+        .map(|(from, to)| Segment::from_points(vertices[*from], vertices[*to]));
+    if crossings_contain(segments, (vertices[p] + vertices[q]) / 2.0) {
+        return Location::Inside;
+    }
+    return Location::Outside;
+}
+
+// Even-odd rule over all the rings
+fn ring_set_contains(rings: &[Vec<Point>], point: Point) -> bool {
+    return crossings_contain(rings.iter().flat_map(|r| ring_segments(r)), point);
+}
+
+// Even-odd rule over the segments of closed rings
+fn crossings_contain(segments: impl Iterator<Item = Segment>, point: Point) -> bool {
+    let mut inside = false;
+    for segment in segments {
+        let (a, b) = (segment.a, segment.b);
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    return inside;
+}
+
+// Angle from `incoming` to `outgoing`, from -π (going back) to π
+fn turn(incoming: Vector, outgoing: Vector) -> f64 {
+    let (incoming, outgoing) = (incoming.unit(), outgoing.unit());
+    let (cross, dot) = (incoming ^ outgoing, incoming * outgoing);
+    if cross.abs() < EPSILON && dot < 0.0 {
+        return -consts::PI;
+    }
+    return cross.atan2(dot);
+}
+
+// Follows the kept edges into closed rings. Where several rings meet at a
+// vertex, the leftmost turn is taken, so that each ring stays simple.
+fn chain(vertices: &[Point], edges: &[(usize, usize)]) -> Vec<Vec<Point>> {
+    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, (from, _)) in edges.iter().enumerate() {
+        outgoing.entry(*from).or_default().push(i);
+    }
+    let direction = |edge: usize| vertices[edges[edge].1] - vertices[edges[edge].0];
+
+    let mut used = vec![false; edges.len()];
+    let mut rings = Vec::new();
+    for start in 0..edges.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let mut ring = Vec::new();
+        let mut current = start;
+        loop {
+            let (from, to) = edges[current];
+            ring.push(vertices[from]);
+            let mut candidates: Vec<usize> = outgoing
+                .get(&to)
+                .into_iter()
+                .flatten()
+                .cloned()
+                .filter(|e| !used[*e])
+                .collect();
+            if to == edges[start].0 {
+                candidates.push(start);
+            }
+            let incoming = direction(current);
+            let next = candidates.into_iter().max_by(|e1, e2| {
+                turn(incoming, direction(*e1))
+                    .partial_cmp(&turn(incoming, direction(*e2)))
+                    .unwrap()
+            });
+            match next {
+                Some(next) if next == start => break,
+                Some(next) => {
+                    used[next] = true;
+                    current = next;
+                }
+                // Left open by numerical trouble, so dropped
+                None => {
+                    ring.clear();
+                    break;
+                }
+            }
+        }
+        rings.push(ring);
+    }
+    return rings;
+}
+
+// Drops repeated and collinear vertices
+fn cleaned(ring: Vec<Point>) -> Vec<Point> {
+    let mut ring = ring;
+    let mut i = 0;
+    while ring.len() >= 3 && i < ring.len() {
+        let len = ring.len();
+        let previous = ring[(i + len - 1) % len];
+        let next = ring[(i + 1) % len];
+        let (a, b) = (ring[i] - previous, next - ring[i]);
+        let straight = (a ^ b).abs() <= EPSILON * a.norm() * b.norm() && a * b >= 0.0;
+        if a.norm() < EPSILON || straight {
+            ring.remove(i);
+            i = i.saturating_sub(1);
+        } else {
+            i += 1;
+        }
+    }
+    return ring;
+}
+
+// Gives each hole to the smallest outline around it
+fn assemble(rings: Vec<Vec<Point>>) -> Vec<PolygonWithHoles> {
+    let rings: Vec<Vec<Point>> = rings
+        .into_iter()
+        .map(cleaned)
+        .filter(|ring| ring.len() >= 3 && signed_area(ring).abs() > EPSILON)
+        .collect();
+    let (outlines, holes): (Vec<Vec<Point>>, Vec<Vec<Point>>) =
+        rings.into_iter().partition(|ring| signed_area(ring) > 0.0);
+
+    let mut result: Vec<PolygonWithHoles> = outlines
+        .into_iter()
+        .map(|vertices| PolygonWithHoles {
+            outline: Polygon { vertices },
+            holes: vec![],
+        })
+        .collect();
+    for hole in holes {
+        let inside = (hole[0] + hole[1]) / 2.0;
+        let owner = (0..result.len())
+            .filter(|i| ring_set_contains(&[result[*i].outline.vertices.clone()], inside))
+            .min_by(|i, j| {
+                let (area_i, area_j) = (result[*i].outline.area(), result[*j].outline.area());
+                area_i.partial_cmp(&area_j).unwrap()
+            });
+        if let Some(owner) = owner {
+            result[owner].holes.push(Polygon { vertices: hole });
+        }
+    }
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::square;
+    use similar::assert_similar;
+    use similar::Similar;
+
+    fn total_area(result: &[PolygonWithHoles]) -> f64 {
+        return result.iter().map(|p| p.area()).sum();
+    }
+
+    #[test]
+    fn overlapping_squares() {
+        let a = square(0.0, 0.0, 2.0);
+        let b = square(1.0, 1.0, 2.0);
+        let union = a.union(&b);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].outline.vertices.len(), 8);
+        assert_similar!(total_area(&union), 7.0);
+
+        let intersection = a.intersection(&b);
+        assert_eq!(intersection.len(), 1);
+        assert_similar!(&intersection[0].outline, &square(1.0, 1.0, 1.0));
+
+        assert_similar!(total_area(&a.difference(&b)), 3.0);
+        let xor = a.xor(&b);
+        assert_eq!(xor.len(), 2);
+        assert_similar!(total_area(&xor), 6.0);
+    }
+
+    #[test]
+    fn disjoint_squares() {
+        let a = square(0.0, 0.0, 1.0);
+        let b = square(3.0, 0.0, 1.0);
+        assert_eq!(a.union(&b).len(), 2);
+        assert_eq!(a.intersection(&b), vec![]);
+        let difference = a.difference(&b);
+        assert_eq!(difference.len(), 1);
+        assert_similar!(&difference[0].outline, &a);
+    }
+
+    #[test]
+    fn crater_inside_makes_a_hole() {
+        let ground = square(-5.0, -5.0, 10.0);
+        let crater = Polygon::new(
+            &(0..16)
+                .map(|i| Point::from_polar(i as f64 * consts::PI / 8.0, 1.0))
+                .collect(),
+        );
+        let result = ground.difference(&crater);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].holes.len(), 1);
+        assert_similar!(result[0].area(), 100.0 - crater.area());
+        assert!(!result[0].contains_point(Point::zero()));
+        assert!(result[0].contains_point(Point::new(2.0, 0.0)));
+
+        // Filling the hole back
+        let filled = result[0].union(&PolygonWithHoles::from(square(-2.0, -2.0, 4.0)));
+        assert_eq!(filled.len(), 1);
+        assert_eq!(filled[0].holes, vec![]);
+        assert_similar!(filled[0].area(), 100.0);
+    }
+
+    #[test]
+    fn crater_on_the_surface() {
+        let ground = square(-5.0, -10.0, 10.0);
+        let crater = square(-1.0, -1.0, 2.0);
+        let result = ground.difference(&crater);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].holes, vec![]);
+        assert_eq!(result[0].outline.vertices.len(), 8);
+        assert_similar!(result[0].area(), 98.0);
+    }
+
+    #[test]
+    fn tiles_merge_along_shared_edges() {
+        let tiles: Vec<Polygon> = (0..4).map(|i| square(i as f64, 0.0, 1.0)).collect();
+        let mut merged = PolygonWithHoles::from(tiles[0].clone());
+        for tile in tiles[1..].iter() {
+            let union = merged.union(&PolygonWithHoles::from(tile.clone()));
+            assert_eq!(union.len(), 1);
+            merged = union[0].clone();
+        }
+        let expected = Polygon::new(&vec![
+            Point::new(4.0, 0.0),
+            Point::new(4.0, 1.0),
+            Point::new(0.0, 1.0),
+            Point::new(0.0, 0.0),
+        ]);
+        assert_similar!(&merged.outline, &expected);
+
+        // Sharing only part of an edge
+        let offset = square(1.0, 0.5, 1.0);
+        let union = tiles[0].union(&offset);
+        assert_eq!(union.len(), 1);
+        assert_similar!(union[0].area(), 2.0);
+        assert_eq!(tiles[0].intersection(&offset), vec![]);
+    }
+
+    #[test]
+    fn identical_polygons() {
+        let a = square(0.0, 0.0, 1.0);
+        let union = a.union(&a);
+        assert_eq!(union.len(), 1);
+        assert_similar!(&union[0].outline, &a);
+        let intersection = a.intersection(&a);
+        assert_eq!(intersection.len(), 1);
+        assert_similar!(&intersection[0].outline, &a);
+        assert_eq!(a.difference(&a), vec![]);
+        assert_eq!(a.xor(&a), vec![]);
+    }
+
+    #[test]
+    fn touching_corners_stay_apart() {
+        let a = square(0.0, 0.0, 1.0);
+        let b = square(1.0, 1.0, 1.0);
+        let union = a.union(&b);
+        assert_eq!(union.len(), 2);
+        assert_similar!(total_area(&union), 2.0);
+    }
+
+    #[test]
+    fn concave_and_clockwise() {
+        // A U shape, clockwise
+        let u = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(0.0, 3.0),
+            Point::new(1.0, 3.0),
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 1.0),
+            Point::new(2.0, 3.0),
+            Point::new(3.0, 3.0),
+            Point::new(3.0, 0.0),
+        ]);
+        let bar = Polygon::new(&vec![
+            Point::new(-1.0, 2.0),
+            Point::new(4.0, 2.0),
+            Point::new(4.0, 2.5),
+            Point::new(-1.0, 2.5),
+        ]);
+        let intersection = u.intersection(&bar);
+        assert_eq!(intersection.len(), 2);
+        assert_similar!(total_area(&intersection), 1.0);
+
+        // Closing the U makes a hole
+        let union = u.union(&bar);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].holes.len(), 1);
+        assert_similar!(union[0].holes[0].area(), 1.0);
+        assert_similar!(union[0].area(), 7.0 + 2.5 - 1.0);
+    }
+
+    #[test]
+    fn near_coincident_vertices() {
+        let a = Polygon::new(&vec![
+            Point::new(1.5, 2.5000000004756675),
+            Point::new(2.5, 0.5),
+            Point::new(3.0, 3.0),
+        ]);
+        let b = Polygon::new(&vec![
+            Point::new(0.5000000000163495, 1.4999999985743402),
+            Point::new(1.0, 0.5000000014000473),
+            Point::new(2.5000000016368955, 0.5000000013688208),
+            Point::new(2.0, 1.0),
+        ]);
+        let intersection = total_area(&a.intersection(&b));
+        let union = total_area(&a.union(&b));
+        assert_similar!(union, a.area() + b.area() - intersection);
+        assert_similar!(total_area(&a.difference(&b)), a.area() - intersection);
+        assert_similar!(total_area(&a.xor(&b)), union - intersection);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::geometry::rounded::sweep_point;
+use crate::Aabb;
+use crate::MassData;
+use crate::Point;
+use crate::Ray;
+use crate::RayHit;
+use crate::Segment;
+use crate::Vector;
+
+use similar::Similar;
+use similar_derive::Similar;
+use std::f64::consts;
+
+/// A segment inflated by a radius: a rectangle with two half circles as ends.
+#[derive(Copy, Clone, Debug, PartialEq, Similar)]
+pub struct Capsule {
+    pub segment: Segment,
+    pub radius: f64,
+}
+
+impl Capsule {
+    pub fn new(segment: Segment, radius: f64) -> Capsule {
+        Capsule { segment, radius }
+    }
+
+    // Accessors
+    pub fn aabb(&self) -> Aabb {
+        return Aabb::from_points(&[self.segment.a, self.segment.b]).expanded(self.radius);
+    }
+
+    pub fn width(&self) -> f64 {
+        return self.right() - self.left();
+    }
+
+    pub fn height(&self) -> f64 {
+        return self.top() - self.bottom();
+    }
+
+    pub fn center(&self) -> Point {
+        return (self.segment.a + self.segment.b) / 2.0;
+    }
+
+    pub fn top(&self) -> f64 {
+        return f64::max(self.segment.a.y, self.segment.b.y) + self.radius;
+    }
+
+    pub fn bottom(&self) -> f64 {
+        return f64::min(self.segment.a.y, self.segment.b.y) - self.radius;
+    }
+
+    pub fn left(&self) -> f64 {
+        return f64::min(self.segment.a.x, self.segment.b.x) - self.radius;
+    }
+
+    pub fn right(&self) -> f64 {
+        return f64::max(self.segment.a.x, self.segment.b.x) + self.radius;
+    }
+
+    pub fn area(&self) -> f64 {
+        let length = self.segment.direction().norm();
+        return 2.0 * self.radius * length + consts::PI * self.radius * self.radius;
+    }
+
+    // Around the center: a rectangle, plus two half discs whose centroids
+    // lie `4r / 3π` beyond the ends of the segment
+    pub fn moment_of_inertia(&self, density: f64) -> f64 {
+        let length = self.segment.direction().norm();
+        let r2 = self.radius * self.radius;
+        let half = length / 2.0;
+        let box_mass = density * 2.0 * self.radius * length;
+        let circle_mass = density * consts::PI * r2;
+        let offset = 4.0 * self.radius / (3.0 * consts::PI);
+        return box_mass * (4.0 * r2 + length * length) / 12.0
+            + circle_mass * (r2 / 2.0 + half * half + 2.0 * half * offset);
+    }
+
+    pub fn mass_data(&self, density: f64) -> MassData {
+        return MassData {
+            mass: density * self.area(),
+            center: self.center(),
+            inertia: self.moment_of_inertia(density),
+        };
+    }
+
+    pub fn contains_point(&self, point: Point) -> bool {
+        return self.segment.closest_point(point).distance(point) <= self.radius;
+    }
+
+    // Returns `None` if the ray starts inside the capsule
+    pub fn raycast(&self, ray: &Ray, max_distance: f64) -> Option<RayHit> {
+        let (distance, normal) = sweep_point(
+            &[self.segment.a, self.segment.b],
+            self.radius,
+            ray.origin,
+            ray.direction,
+        )?;
+        if distance > max_distance {
+            return None;
+        }
+        return Some(RayHit {
+            distance,
+            point: ray.point_at(distance),
+            normal,
+        });
+    }
+
+    // Mutators
+    pub fn move_xy(&mut self, offset: Vector) {
+        self.segment.a += offset;
+        self.segment.b += offset;
+    }
+    pub fn move_x(&mut self, offset_x: f64) {
+        self.move_xy(Vector::new(offset_x, 0.0));
+    }
+    pub fn move_y(&mut self, offset_y: f64) {
+        self.move_xy(Vector::new(0.0, offset_y));
+    }
+    pub fn rotate(&mut self, angle: f64) {
+        self.rotate_about(self.center(), angle);
+    }
+    pub fn rotate_about(&mut self, center: Point, angle: f64) {
+        self.segment.a = center + (self.segment.a - center).rotated_by(angle);
+        self.segment.b = center + (self.segment.b - center).rotated_by(angle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar::assert_similar;
+
+    fn horizontal() -> Capsule {
+        Capsule::new(
+            Segment::from_points(Point::new(-1.0, 0.0), Point::new(1.0, 0.0)),
+            0.5,
+        )
+    }
+
+    #[test]
+    fn bounds() {
+        let capsule = horizontal();
+        assert_similar!(capsule.width(), 3.0);
+        assert_similar!(capsule.height(), 1.0);
+        assert_similar!(capsule.left(), -1.5);
+        assert_similar!(capsule.top(), 0.5);
+    }
+
+    #[test]
+    fn degenerate_mass_is_a_circle() {
+        let point = Segment::from_points(Point::new(1.0, 1.0), Point::new(1.0, 1.0));
+        let capsule = Capsule::new(point, 2.0);
+        assert_similar!(capsule.area(), 4.0 * consts::PI);
+        assert_similar!(capsule.moment_of_inertia(1.0), 4.0 * consts::PI * 4.0 / 2.0);
+    }
+
+    #[test]
+    fn inertia_matches_polygon() {
+        let capsule = horizontal();
+        // Half circles around each end of the segment
+        let vertices: Vec<Point> = (0..=1000)
+            .map(|i| Point::from_polar(i as f64 * consts::PI / 1000.0 - consts::PI / 2.0, 0.5))
+            .map(|p| p + Vector::new(1.0, 0.0))
+            .chain(
+                (0..=1000)
+                    .map(|i| {
+                        Point::from_polar(i as f64 * consts::PI / 1000.0 + consts::PI / 2.0, 0.5)
+                    })
+                    .map(|p| p + Vector::new(-1.0, 0.0)),
+            )
+            .collect();
+        let polygon = crate::Polygon::new(&vertices);
+        assert_similar!(capsule.area(), polygon.area(), 1e-4);
+        assert_similar!(
+            capsule.moment_of_inertia(1.0),
+            polygon.moment_of_inertia(1.0),
+            1e-4
+        );
+    }
+
+    #[test]
+    fn raycast_side_and_end() {
+        let capsule = horizontal();
+        let down = Ray::new(Point::new(0.5, 3.0), Vector::new(0.0, -1.0));
+        let hit = capsule.raycast(&down, 10.0).unwrap();
+        assert_similar!(hit.distance, 2.5);
+        assert_similar!(hit.point, Point::new(0.5, 0.5));
+        assert_similar!(hit.normal, Vector::new(0.0, 1.0));
+        assert_eq!(capsule.raycast(&down, 2.0), None);
+
+        let left = Ray::new(Point::new(5.0, 0.0), Vector::new(-1.0, 0.0));
+        let hit = capsule.raycast(&left, 10.0).unwrap();
+        assert_similar!(hit.point, Point::new(1.5, 0.0));
+        assert_similar!(hit.normal, Vector::new(1.0, 0.0));
+
+        let inside = Ray::new(Point::new(0.0, 0.0), Vector::new(1.0, 0.0));
+        assert_eq!(capsule.raycast(&inside, 10.0), None);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::geometry::rounded::sweep_point;
+use crate::Aabb;
+use crate::MassData;
+use crate::Point;
+use crate::Ray;
+use crate::RayHit;
+use crate::Vector;
+
+use similar::Similar;
+use similar_derive::Similar;
+use std::f64::consts;
+
+#[derive(Copy, Clone, Debug, PartialEq, Similar)]
+pub struct Circle {
+    pub center: Point,
+    pub radius: f64,
+}
+
+impl Circle {
+    pub fn new(center: Point, radius: f64) -> Circle {
+        Circle { center, radius }
+    }
+
+    // Accessors
+    pub fn aabb(&self) -> Aabb {
+        let radius = Vector::new(self.radius, self.radius);
+        return Aabb::new(self.center - radius, self.center + radius);
+    }
+
+    pub fn width(&self) -> f64 {
+        return 2.0 * self.radius;
+    }
+
+    pub fn height(&self) -> f64 {
+        return 2.0 * self.radius;
+    }
+
+    pub fn top(&self) -> f64 {
+        return self.center.y + self.radius;
+    }
+
+    pub fn bottom(&self) -> f64 {
+        return self.center.y - self.radius;
+    }
+
+    pub fn left(&self) -> f64 {
+        return self.center.x - self.radius;
+    }
+
+    pub fn right(&self) -> f64 {
+        return self.center.x + self.radius;
+    }
+
+    pub fn area(&self) -> f64 {
+        return consts::PI * self.radius * self.radius;
+    }
+
+    // Around the center
+    pub fn moment_of_inertia(&self, density: f64) -> f64 {
+        return density * self.area() * self.radius * self.radius / 2.0;
+    }
+
+    pub fn mass_data(&self, density: f64) -> MassData {
+        return MassData {
+            mass: density * self.area(),
+            center: self.center,
+            inertia: self.moment_of_inertia(density),
+        };
+    }
+
+    pub fn contains_point(&self, point: Point) -> bool {
+        return point.distance(self.center) <= self.radius;
+    }
+
+    // Returns `None` if the ray starts inside the circle
+    pub fn raycast(&self, ray: &Ray, max_distance: f64) -> Option<RayHit> {
+        let (distance, normal) =
+            sweep_point(&[self.center], self.radius, ray.origin, ray.direction)?;
+        if distance > max_distance {
+            return None;
+        }
+        return Some(RayHit {
+            distance,
+            point: ray.point_at(distance),
+            normal,
+        });
+    }
+
+    // Mutators
+    pub fn move_xy(&mut self, offset: Vector) {
+        self.center += offset;
+    }
+    pub fn move_x(&mut self, offset_x: f64) {
+        self.move_xy(Vector::new(offset_x, 0.0));
+    }
+    pub fn move_y(&mut self, offset_y: f64) {
+        self.move_xy(Vector::new(0.0, offset_y));
+    }
+    pub fn rotate_about(&mut self, center: Point, angle: f64) {
+        self.center = center + (self.center - center).rotated_by(angle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar::assert_similar;
+
+    #[test]
+    fn bounds() {
+        let circle = Circle::new(Point::new(1.0, 2.0), 0.5);
+        assert_similar!(circle.width(), 1.0);
+        assert_similar!(circle.height(), 1.0);
+        assert_similar!(circle.left(), 0.5);
+        assert_similar!(circle.right(), 1.5);
+        assert_similar!(circle.top(), 2.5);
+        assert_similar!(circle.bottom(), 1.5);
+    }
+
+    #[test]
+    fn mass_data() {
+        let circle = Circle::new(Point::new(1.0, 2.0), 2.0);
+        let expected = MassData {
+            mass: 3.0 * 4.0 * consts::PI,
+            center: Point::new(1.0, 2.0),
+            inertia: 3.0 * 4.0 * consts::PI * 4.0 / 2.0,
+        };
+        assert_similar!(circle.mass_data(3.0), expected);
+    }
+
+    #[test]
+    fn raycast() {
+        let circle = Circle::new(Point::new(3.0, 0.0), 1.0);
+        let ray = Ray::new(Point::zero(), Vector::new(1.0, 0.0));
+        let expected = RayHit {
+            distance: 2.0,
+            point: Point::new(2.0, 0.0),
+            normal: Vector::new(-1.0, 0.0),
+        };
+        assert_similar!(circle.raycast(&ray, 5.0), Some(expected));
+        let ray = Ray::new(Point::zero(), Vector::new(0.0, 1.0));
+        assert_similar!(circle.raycast(&ray, 5.0), Option::<RayHit>::None);
+    }
+
+    #[test]
+    fn rotate_about() {
+        let mut circle = Circle::new(Point::new(1.0, 0.0), 0.5);
+        circle.rotate_about(Point::zero(), consts::PI / 2.0);
+        assert_similar!(circle.center, Point::new(0.0, 1.0));
+    }
+}
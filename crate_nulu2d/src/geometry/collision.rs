@@ -1,78 +1,197 @@
+use crate::Circle;
+use crate::Feature;
 use crate::Point;
 use crate::Polygon;
 use crate::Segment;
 use crate::Vector;
 
-fn mtv(a: &Polygon, b: &Polygon) -> Option<Vector> {
-    let mut mtv = Vector::new(f64::INFINITY, f64::INFINITY);
+use similar::Similar;
+use similar_derive::Similar;
+
+/// How deep two shapes overlap, and along which direction.
+/// The normal is a unit vector pointing from the first shape towards the
+/// second one, so moving the second shape by `normal * depth` separates them.
+#[derive(Copy, Clone, Debug, PartialEq, Similar)]
+pub struct Penetration {
+    pub depth: f64,
+    pub normal: Vector,
+}
+
+/// Returns whether two convex polygons overlap. Touching borders count as
+/// overlapping.
+pub fn overlaps(a: &Polygon, b: &Polygon) -> bool {
+    return penetration(a, b).is_some();
+}
+
+/// Returns the penetration of two convex polygons, using the separating axis
+/// with the least overlap. Returns `None` if they don't overlap.
+pub fn penetration(a: &Polygon, b: &Polygon) -> Option<Penetration> {
+    let mut penetration: Option<Penetration> = None;
     let axes = a
         .segments()
         .into_iter()
-        .chain(b.segments().into_iter())
-        .map(|s| s.direction().perp().unit());
+        .chain(b.segments())
+        .map(|s| s.direction())
+        .filter(|d| d.norm() > f64::EPSILON)
+        .map(|d| d.perp().unit());
 
     for axis in axes {
-        let mut mina = a
+        let mina = a
             .vertices
             .iter()
             .map(|v| *v * axis)
             .fold(f64::INFINITY, f64::min);
-        let mut maxa = a
+        let maxa = a
             .vertices
             .iter()
             .map(|v| *v * axis)
             .fold(f64::NEG_INFINITY, f64::max);
-        let mut minb = b
+        let minb = b
             .vertices
             .iter()
             .map(|v| *v * axis)
             .fold(f64::INFINITY, f64::min);
-        let mut maxb = b
+        let maxb = b
             .vertices
             .iter()
             .map(|v| *v * axis)
             .fold(f64::NEG_INFINITY, f64::max);
 
-        let mut overlap: f64;
-        let mut neg = false;
-
-        if mina >= minb {
-            neg = !neg;
-            let (_mina, _maxa) = (-maxa, -mina);
-            let (_minb, _maxb) = (-maxb, -minb);
-            mina = _mina;
-            minb = _minb;
-            maxa = _maxa;
-            maxb = _maxb;
+        if maxa < minb || maxb < mina {
+            return None;
         }
 
-        // find overlap
-        if maxb <= maxa {
-            overlap = maxb - minb;
-            if minb - mina < maxa - maxb {
-                overlap += minb - mina;
-            } else {
-                overlap += maxa - maxb;
-                neg = !neg;
-            }
-        } else if minb <= maxa {
-            overlap = maxa - minb
+        // b can leave a through either side of the projection
+        let (overlap, neg) = if maxa - minb <= maxb - mina {
+            (maxa - minb, false)
         } else {
-            return None;
+            (maxb - mina, true)
+        };
+
+        // penetration update
+        if penetration.is_none_or(|p| overlap < p.depth) {
+            penetration = Some(Penetration {
+                depth: overlap,
+                normal: if neg { axis * -1.0 } else { axis },
+            });
         }
+    }
+    return penetration;
+}
+
+// Returns the edge of `a` whose outward normal separates `b` the most, along
+// with that separation (negative if they overlap along that axis)
+pub(crate) fn max_separation(a: &Polygon, b: &Polygon) -> (usize, f64) {
+    let mut max_separation = (0, f64::NEG_INFINITY);
+    for (i, normal) in a.normals().into_iter().enumerate() {
+        let separation = b
+            .vertices
+            .iter()
+            .map(|v| (*v - a.vertices[i]) * normal)
+            .fold(f64::INFINITY, f64::min);
+        if separation > max_separation.1 {
+            max_separation = (i, separation);
+        }
+    }
+    return max_separation;
+}
+
+/// Returns the penetration of two circles, or `None` if they don't overlap.
+pub fn circles_penetration(a: &Circle, b: &Circle) -> Option<Penetration> {
+    let difference = b.center - a.center;
+    let distance = difference.norm();
+    if distance > a.radius + b.radius {
+        return None;
+    }
+    // Concentric circles can be pushed apart in any direction
+    let normal = if distance > f64::EPSILON {
+        difference / distance
+    } else {
+        Vector::new(0.0, 1.0)
+    };
+    return Some(Penetration {
+        depth: a.radius + b.radius - distance,
+        normal,
+    });
+}
+
+/// Returns the penetration of a convex polygon and a circle, or `None` if
+/// they don't overlap. The normal points from the polygon towards the circle.
+pub fn polygon_circle_penetration(polygon: &Polygon, circle: &Circle) -> Option<Penetration> {
+    let (normal, depth, _) = polygon_circle_contact(polygon, circle, 0.0)?;
+    return Some(Penetration { depth, normal });
+}
 
-        // assure separation
-        overlap += 1e-9;
+// Normal (from the polygon towards the circle), penetration depth and
+// nearest feature of the polygon, or `None` if the circle is further than
+// `margin` from the polygon
+pub(crate) fn polygon_circle_contact(
+    polygon: &Polygon,
+    circle: &Circle,
+    margin: f64,
+) -> Option<(Vector, f64, Feature)> {
+    let normals = polygon.normals();
+    let (edge, separation) = normals
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (i, (circle.center - polygon.vertices[i]) * *n))
+        .fold(
+            (0, f64::NEG_INFINITY),
+            |max, s| if s.1 > max.1 { s } else { max },
+        );
+
+    // Center inside the polygon: push it out through the nearest edge
+    if separation <= 0.0 {
+        return Some((
+            normals[edge],
+            circle.radius - separation,
+            Feature::Edge(edge),
+        ));
+    }
 
-        // mtv update
-        if overlap < mtv.norm() {
-            mtv = axis * (if neg { -overlap } else { overlap });
+    let (closest, feature) = closest_boundary_point(polygon, circle.center);
+    let distance = closest.distance(circle.center);
+    if distance > circle.radius + margin {
+        return None;
+    }
+    return Some((
+        (circle.center - closest) / distance,
+        circle.radius - distance,
+        feature,
+    ));
+}
+
+// Point of the border of the polygon nearest to `point`, along with the edge
+// or vertex it lies on
+fn closest_boundary_point(polygon: &Polygon, point: Point) -> (Point, Feature) {
+    let len = polygon.vertices.len();
+    let mut closest = (polygon.vertices[0], Feature::Vertex(0), f64::INFINITY);
+    for (i, segment) in polygon.segments().into_iter().enumerate() {
+        let candidate = segment.closest_point(point);
+        let distance = candidate.distance(point);
+        if distance < closest.2 {
+            let feature = if candidate == segment.a {
+                Feature::Vertex(i)
+            } else if candidate == segment.b {
+                Feature::Vertex((i + 1) % len)
+            } else {
+                Feature::Edge(i)
+            };
+            closest = (candidate, feature, distance);
         }
     }
-    return Some(mtv);
+    return (closest.0, closest.1);
 }
 
-fn containing(shape: &Polygon, point: Point) -> bool {
+/// Returns the minimum translation vector that, applied to `b`, separates it
+/// from `a`. Returns `None` if they don't overlap.
+pub fn mtv(a: &Polygon, b: &Polygon) -> Option<Vector> {
+    // assure separation
+    return penetration(a, b).map(|p| p.normal * (p.depth + 1e-9));
+}
+
+/// Returns whether a point lies inside a convex polygon (or on its border).
+pub fn contains_point(shape: &Polygon, point: Point) -> bool {
     let shape_point = shape.center();
     for segment in shape.segments().iter() {
         let orthogonal = segment.direction().perp().unit();
@@ -91,7 +210,10 @@ fn containing(shape: &Polygon, point: Point) -> bool {
     return true;
 }
 
-fn parametric_intersection(la: Segment, lb: Segment) -> Option<(f64, f64)> {
+/// Returns the parameters `(t1, t2)` at which the lines through both segments
+/// cross, so that the crossing point is `la.a + la.direction() * t1` (and
+/// likewise for `lb`). Returns `None` if the segments are parallel.
+pub fn parametric_intersection(la: Segment, lb: Segment) -> Option<(f64, f64)> {
     let c = la.center();
     let v = la.direction();
     let d = lb.center();
@@ -108,7 +230,8 @@ fn parametric_intersection(la: Segment, lb: Segment) -> Option<(f64, f64)> {
     }
 }
 
-fn intersection(la: Segment, lb: Segment) -> Option<Point> {
+/// Returns the point where two segments cross, if they do.
+pub fn intersection(la: Segment, lb: Segment) -> Option<Point> {
     match parametric_intersection(la, lb) {
         Some((t1, t2))
             if (t1 >= 0.0 - f64::EPSILON && t1 <= 1.0 + f64::EPSILON)
@@ -123,9 +246,9 @@ fn intersection(la: Segment, lb: Segment) -> Option<Point> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::square;
     use crate::Point;
     use similar::assert_similar;
-    use similar::Similar;
 
     #[test]
     fn parametric_intersection_regular() {
@@ -141,6 +264,13 @@ mod tests {
         assert_eq!(-2.0, inter.1);
     }
 
+    #[test]
+    fn parametric_intersection_similar() {
+        let l1 = Segment::from_arrow(Point::new(0.0, 0.0), Point::new(2.0, 0.0));
+        let l2 = Segment::from_arrow(Point::new(1.0, -1.0), Point::new(0.0, 3.0));
+        assert_similar!(parametric_intersection(l1, l2), Some((0.5, 1.0 / 3.0)));
+    }
+
     #[test]
     fn test_parametric_intersection_parallel() {
         let l1 = Segment::from_arrow(Point::new(0.0, 3.0), Point::new(1.0, -1.0));
@@ -275,6 +405,75 @@ mod tests {
         assert!(mtv(&p, &q).unwrap().norm() > 0.0);
     }
 
+    #[test]
+    fn mtv_contained() {
+        let p = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(10.0, 10.0),
+            Point::new(0.0, 10.0),
+        ]);
+        let q = Polygon::new(&vec![
+            Point::new(1.0, 4.0),
+            Point::new(2.0, 4.0),
+            Point::new(2.0, 6.0),
+            Point::new(1.0, 6.0),
+        ]);
+        assert_similar!(mtv(&p, &q), Some(Point::new(-2.0, 0.0)));
+        assert_similar!(mtv(&q, &p), Some(Point::new(2.0, 0.0)));
+    }
+
+    #[test]
+    fn penetration_depth_and_normal() {
+        let p = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(0.0, 4.0),
+            Point::new(4.0, 4.0),
+            Point::new(4.0, 0.0),
+        ]);
+        let q = Polygon::new(&vec![
+            Point::new(3.5, 1.0),
+            Point::new(6.0, 1.0),
+            Point::new(6.0, 3.0),
+            Point::new(3.5, 3.0),
+        ]);
+        let expected = Penetration {
+            depth: 0.5,
+            normal: Vector::new(1.0, 0.0),
+        };
+        assert_similar!(penetration(&p, &q), Some(expected));
+
+        let expected = Penetration {
+            depth: 0.5,
+            normal: Vector::new(-1.0, 0.0),
+        };
+        assert_similar!(penetration(&q, &p), Some(expected));
+    }
+
+    #[test]
+    fn overlaps_true_and_false() {
+        let p = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(0.0, 2.0),
+            Point::new(2.0, 2.0),
+            Point::new(2.0, 0.0),
+        ]);
+        let q = Polygon::new(&vec![
+            Point::new(1.0, 1.0),
+            Point::new(3.0, 1.0),
+            Point::new(3.0, 3.0),
+        ]);
+        let r = Polygon::new(&vec![
+            Point::new(3.0, 0.0),
+            Point::new(4.0, 0.0),
+            Point::new(4.0, 1.0),
+        ]);
+        assert!(overlaps(&p, &q));
+        assert!(overlaps(&q, &p));
+        assert!(!overlaps(&p, &r));
+        assert_similar!(penetration(&p, &r), Option::<Penetration>::None);
+    }
+
     #[test]
     fn contains_false() {
         let shape = Polygon::new(&vec![
@@ -288,7 +487,7 @@ mod tests {
             Point::new(0.0, 2.0),
         ]);
         let point = Point::new(0.5 - 0.1, 0.5 - 0.1);
-        assert!(!containing(&shape, point));
+        assert!(!contains_point(&shape, point));
     }
 
     #[test]
@@ -304,6 +503,63 @@ mod tests {
             Point::new(0.0, 2.0),
         ]);
         let point = Point::new(0.5 + 0.1, 0.5 + 0.1);
-        assert!(containing(&shape, point));
+        assert!(contains_point(&shape, point));
+    }
+
+    #[test]
+    fn circles_penetration_overlapping() {
+        let a = Circle::new(Point::new(0.0, 0.0), 1.0);
+        let b = Circle::new(Point::new(1.5, 0.0), 1.0);
+        let expected = Penetration {
+            depth: 0.5,
+            normal: Vector::new(1.0, 0.0),
+        };
+        assert_similar!(circles_penetration(&a, &b), Some(expected));
+        let c = Circle::new(Point::new(0.0, 2.5), 1.0);
+        assert_similar!(circles_penetration(&a, &c), Option::<Penetration>::None);
+    }
+
+    #[test]
+    fn polygon_circle_penetration_edge() {
+        let circle = Circle::new(Point::new(0.5, 1.25), 0.5);
+        let expected = Penetration {
+            depth: 0.25,
+            normal: Vector::new(0.0, 1.0),
+        };
+        assert_similar!(
+            polygon_circle_penetration(&square(0.0, 0.0, 1.0), &circle),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn polygon_circle_penetration_corner() {
+        let circle = Circle::new(Point::new(1.3, 1.4), 0.6);
+        let expected = Penetration {
+            depth: 0.1,
+            normal: Vector::new(0.6, 0.8),
+        };
+        assert_similar!(
+            polygon_circle_penetration(&square(0.0, 0.0, 1.0), &circle),
+            Some(expected)
+        );
+        let circle = Circle::new(Point::new(1.3, 1.4), 0.4);
+        assert_similar!(
+            polygon_circle_penetration(&square(0.0, 0.0, 1.0), &circle),
+            Option::<Penetration>::None
+        );
+    }
+
+    #[test]
+    fn polygon_circle_penetration_center_inside() {
+        let circle = Circle::new(Point::new(0.9, 0.5), 0.2);
+        let expected = Penetration {
+            depth: 0.3,
+            normal: Vector::new(1.0, 0.0),
+        };
+        assert_similar!(
+            polygon_circle_penetration(&square(0.0, 0.0, 1.0), &circle),
+            Some(expected)
+        );
     }
 }
@@ -0,0 +1,434 @@
+use crate::geometry::collision::circles_penetration;
+use crate::geometry::collision::max_separation;
+use crate::geometry::collision::polygon_circle_contact;
+use crate::Circle;
+use crate::Point;
+use crate::Polygon;
+use crate::Vector;
+
+use similar::Similar;
+use similar_derive::Similar;
+
+/// Distance under which two polygons are considered to be touching.
+pub const CONTACT_MARGIN: f64 = 1e-6;
+
+/// A vertex or an edge of a polygon, by index.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Feature {
+    Vertex(usize),
+    Edge(usize),
+}
+
+/// The pair of features (one of each polygon) that produced a contact point.
+/// As long as two polygons keep touching in the same way, their contact
+/// points keep the same ids, so they can be matched from one step to the next.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FeatureId {
+    pub a: Feature,
+    pub b: Feature,
+}
+
+impl Similar for FeatureId {
+    fn is_similar(self, other: FeatureId, _: f64) -> bool {
+        return self == other;
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Similar)]
+pub struct ContactPoint {
+    pub point: Point,
+    pub depth: f64,
+    pub id: FeatureId,
+}
+
+/// The contact region of two overlapping (or touching) convex polygons.
+/// The normal is a unit vector pointing from the first polygon towards the
+/// second one, and there are one or two points, which lie on the surface of
+/// the incident polygon. Depths are positive when the polygons overlap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContactManifold {
+    pub normal: Vector,
+    pub points: Vec<ContactPoint>,
+}
+
+impl ContactManifold {
+    // The same contact, seen from the other shape
+    pub(crate) fn flipped(self) -> ContactManifold {
+        return ContactManifold {
+            normal: self.normal * -1.0,
+            points: self
+                .points
+                .into_iter()
+                .map(|p| ContactPoint {
+                    point: p.point,
+                    depth: p.depth,
+                    id: FeatureId {
+                        a: p.id.b,
+                        b: p.id.a,
+                    },
+                })
+                .collect(),
+        };
+    }
+}
+
+impl Similar for &ContactManifold {
+    fn is_similar(self, other: &ContactManifold, eps: f64) -> bool {
+        return self.normal.is_similar(other.normal, eps)
+            && self.points[..].is_similar(&other.points[..], eps);
+    }
+}
+
+// A point of the incident edge while it's being clipped, along with the
+// features of the reference and incident polygons it comes from
+#[derive(Copy, Clone)]
+struct ClipVertex {
+    point: Point,
+    reference: Feature,
+    incident: Feature,
+}
+
+/// Builds the contact manifold of two convex polygons, or returns `None` if
+/// they are farther apart than `CONTACT_MARGIN`.
+/// The edge with the largest separation (the SAT axis) is taken as the
+/// reference face, and the most anti-parallel edge of the other polygon (the
+/// incident face) is clipped against its sides.
+pub fn contact_manifold(a: &Polygon, b: &Polygon) -> Option<ContactManifold> {
+    let (edge_a, separation_a) = max_separation(a, b);
+    if separation_a > CONTACT_MARGIN {
+        return None;
+    }
+    let (edge_b, separation_b) = max_separation(b, a);
+    if separation_b > CONTACT_MARGIN {
+        return None;
+    }
+
+    // Prefer a as the reference polygon, so that ties don't flicker
+    let flipped = separation_b > separation_a + 0.1 * CONTACT_MARGIN;
+    let (reference, incident, reference_edge) = if flipped {
+        (b, a, edge_b)
+    } else {
+        (a, b, edge_a)
+    };
+
+    // Reference face
+    let len = reference.vertices.len();
+    let i1 = reference_edge;
+    let i2 = (reference_edge + 1) % len;
+    let v1 = reference.vertices[i1];
+    let v2 = reference.vertices[i2];
+    let normal = reference.normals()[reference_edge];
+    let tangent = (v2 - v1).unit();
+
+    // Incident face
+    let incident_edge = incident
+        .normals()
+        .into_iter()
+        .enumerate()
+        .fold((0, f64::INFINITY), |(best, min), (i, n)| {
+            if n * normal < min {
+                (i, n * normal)
+            } else {
+                (best, min)
+            }
+        })
+        .0;
+    let incident_len = incident.vertices.len();
+    let incident_face = vec![
+        ClipVertex {
+            point: incident.vertices[incident_edge],
+            reference: Feature::Edge(reference_edge),
+            incident: Feature::Vertex(incident_edge),
+        },
+        ClipVertex {
+            point: incident.vertices[(incident_edge + 1) % incident_len],
+            reference: Feature::Edge(reference_edge),
+            incident: Feature::Vertex((incident_edge + 1) % incident_len),
+        },
+    ];
+
+    // Clip against the sides of the reference face
+    let clipped = clip(
+        &incident_face,
+        tangent * -1.0,
+        -(tangent * v1),
+        i1,
+        incident_edge,
+    );
+    if clipped.len() < 2 {
+        return None;
+    }
+    let clipped = clip(&clipped, tangent, tangent * v2, i2, incident_edge);
+    if clipped.len() < 2 {
+        return None;
+    }
+
+    let points: Vec<ContactPoint> = clipped
+        .into_iter()
+        .map(|c| (c, (c.point - v1) * normal))
+        .filter(|(_, separation)| *separation <= CONTACT_MARGIN)
+        .map(|(c, separation)| ContactPoint {
+            point: c.point,
+            depth: -separation,
+            id: if flipped {
+                FeatureId {
+                    a: c.incident,
+                    b: c.reference,
+                }
+            } else {
+                FeatureId {
+                    a: c.reference,
+                    b: c.incident,
+                }
+            },
+        })
+        .collect();
+
+    if points.is_empty() {
+        return None;
+    }
+    return Some(ContactManifold {
+        normal: if flipped { normal * -1.0 } else { normal },
+        points,
+    });
+}
+
+/// Builds the contact manifold of two circles, or returns `None` if they are
+/// farther apart than `CONTACT_MARGIN`. The single point lies on the surface
+/// of `b`.
+pub fn circles_contact_manifold(a: &Circle, b: &Circle) -> Option<ContactManifold> {
+    let margin = Circle::new(b.center, b.radius + CONTACT_MARGIN);
+    let penetration = circles_penetration(a, &margin)?;
+    return Some(ContactManifold {
+        normal: penetration.normal,
+        points: vec![ContactPoint {
+            point: b.center - penetration.normal * b.radius,
+            depth: penetration.depth - CONTACT_MARGIN,
+            id: FeatureId {
+                a: Feature::Vertex(0),
+                b: Feature::Vertex(0),
+            },
+        }],
+    });
+}
+
+/// Builds the contact manifold of a convex polygon and a circle, or returns
+/// `None` if they are farther apart than `CONTACT_MARGIN`. The normal points
+/// from the polygon towards the circle, and the single point lies on the
+/// surface of the circle.
+pub fn polygon_circle_contact_manifold(
+    polygon: &Polygon,
+    circle: &Circle,
+) -> Option<ContactManifold> {
+    let (normal, depth, feature) = polygon_circle_contact(polygon, circle, CONTACT_MARGIN)?;
+    return Some(ContactManifold {
+        normal,
+        points: vec![ContactPoint {
+            point: circle.center - normal * circle.radius,
+            depth,
+            id: FeatureId {
+                a: feature,
+                b: Feature::Vertex(0),
+            },
+        }],
+    });
+}
+
+// Keeps the part of the segment that lies behind the plane `normal * x = offset`.
+// Points created by the clipping are identified by the reference vertex that
+// defines the plane and the incident edge.
+fn clip(
+    segment: &[ClipVertex],
+    normal: Vector,
+    offset: f64,
+    reference_vertex: usize,
+    incident_edge: usize,
+) -> Vec<ClipVertex> {
+    let mut result = Vec::with_capacity(2);
+    let d0 = segment[0].point * normal - offset;
+    let d1 = segment[1].point * normal - offset;
+
+    if d0 <= 0.0 {
+        result.push(segment[0]);
+    }
+    if d1 <= 0.0 {
+        result.push(segment[1]);
+    }
+    if d0 * d1 < 0.0 {
+        let t = d0 / (d0 - d1);
+        result.push(ClipVertex {
+            point: segment[0].point + (segment[1].point - segment[0].point) * t,
+            reference: Feature::Vertex(reference_vertex),
+            incident: Feature::Edge(incident_edge),
+        });
+    }
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::rectangle;
+    use similar::assert_similar;
+
+    #[test]
+    fn resting_box() {
+        let floor = rectangle(0.0, -1.0, 10.0, 0.0);
+        let crate_ = rectangle(2.0, -0.1, 4.0, 1.9);
+        let expected = ContactManifold {
+            normal: Vector::new(0.0, 1.0),
+            points: vec![
+                ContactPoint {
+                    point: Point::new(2.0, -0.1),
+                    depth: 0.1,
+                    id: FeatureId {
+                        a: Feature::Edge(2),
+                        b: Feature::Vertex(0),
+                    },
+                },
+                ContactPoint {
+                    point: Point::new(4.0, -0.1),
+                    depth: 0.1,
+                    id: FeatureId {
+                        a: Feature::Edge(2),
+                        b: Feature::Vertex(1),
+                    },
+                },
+            ],
+        };
+        assert_similar!(&contact_manifold(&floor, &crate_).unwrap(), &expected);
+    }
+
+    #[test]
+    fn reversed_order() {
+        let floor = rectangle(0.0, -1.0, 10.0, 0.0);
+        let crate_ = rectangle(2.0, -0.1, 4.0, 1.9);
+        let manifold = contact_manifold(&crate_, &floor).unwrap();
+        assert_similar!(manifold.normal, Vector::new(0.0, -1.0));
+        assert_eq!(manifold.points.len(), 2);
+        for point in manifold.points.iter() {
+            assert_similar!(point.depth, 0.1);
+            assert_similar!(point.point.y, 0.0);
+            assert_eq!(point.id.b, Feature::Edge(2));
+        }
+    }
+
+    #[test]
+    fn hanging_box_is_clipped() {
+        let floor = rectangle(0.0, -1.0, 10.0, 0.0);
+        let crate_ = rectangle(9.0, -0.1, 11.0, 1.9);
+        let manifold = contact_manifold(&floor, &crate_).unwrap();
+        assert_similar!(manifold.normal, Vector::new(0.0, 1.0));
+        let expected = [
+            ContactPoint {
+                point: Point::new(9.0, -0.1),
+                depth: 0.1,
+                id: FeatureId {
+                    a: Feature::Edge(2),
+                    b: Feature::Vertex(0),
+                },
+            },
+            ContactPoint {
+                point: Point::new(10.0, -0.1),
+                depth: 0.1,
+                id: FeatureId {
+                    a: Feature::Vertex(2),
+                    b: Feature::Edge(0),
+                },
+            },
+        ];
+        assert_similar!(&manifold.points[..], &expected[..]);
+    }
+
+    #[test]
+    fn corner_contact() {
+        let floor = rectangle(0.0, -1.0, 10.0, 0.0);
+        let diamond = Polygon::new(&vec![
+            Point::new(5.0, -0.1),
+            Point::new(6.0, 0.9),
+            Point::new(5.0, 1.9),
+            Point::new(4.0, 0.9),
+        ]);
+        let manifold = contact_manifold(&floor, &diamond).unwrap();
+        assert_similar!(manifold.normal, Vector::new(0.0, 1.0));
+        assert_eq!(manifold.points.len(), 1);
+        assert_similar!(manifold.points[0].point, Point::new(5.0, -0.1));
+        assert_similar!(manifold.points[0].depth, 0.1);
+        assert_eq!(manifold.points[0].id.b, Feature::Vertex(0));
+    }
+
+    #[test]
+    fn touching() {
+        let floor = rectangle(0.0, -1.0, 10.0, 0.0);
+        let crate_ = rectangle(2.0, 0.0, 4.0, 2.0);
+        let manifold = contact_manifold(&floor, &crate_).unwrap();
+        assert_eq!(manifold.points.len(), 2);
+        assert_similar!(manifold.points[0].depth, 0.0);
+    }
+
+    #[test]
+    fn separated() {
+        let floor = rectangle(0.0, -1.0, 10.0, 0.0);
+        let crate_ = rectangle(2.0, 0.1, 4.0, 2.1);
+        assert_eq!(contact_manifold(&floor, &crate_), None);
+    }
+
+    #[test]
+    fn ids_persist_between_frames() {
+        let floor = rectangle(0.0, -1.0, 10.0, 0.0);
+        let mut crate_ = rectangle(2.0, -0.1, 4.0, 1.9);
+        let before = contact_manifold(&floor, &crate_).unwrap();
+        crate_.move_xy(Vector::new(0.5, 0.05));
+        let after = contact_manifold(&floor, &crate_).unwrap();
+        let ids = |m: &ContactManifold| m.points.iter().map(|p| p.id).collect::<Vec<_>>();
+        assert_eq!(ids(&before), ids(&after));
+    }
+
+    #[test]
+    fn resting_ball() {
+        let floor = rectangle(0.0, -1.0, 10.0, 0.0);
+        let ball = Circle::new(Point::new(3.0, 0.4), 0.5);
+        let expected = ContactManifold {
+            normal: Vector::new(0.0, 1.0),
+            points: vec![ContactPoint {
+                point: Point::new(3.0, -0.1),
+                depth: 0.1,
+                id: FeatureId {
+                    a: Feature::Edge(2),
+                    b: Feature::Vertex(0),
+                },
+            }],
+        };
+        assert_similar!(
+            &polygon_circle_contact_manifold(&floor, &ball).unwrap(),
+            &expected
+        );
+        let flipped = polygon_circle_contact_manifold(&floor, &ball)
+            .unwrap()
+            .flipped();
+        assert_similar!(flipped.normal, Vector::new(0.0, -1.0));
+        assert_eq!(flipped.points[0].id.b, Feature::Edge(2));
+    }
+
+    #[test]
+    fn ball_on_corner() {
+        let floor = rectangle(0.0, -1.0, 10.0, 0.0);
+        let ball = Circle::new(Point::new(10.3, 0.4), 0.5);
+        let manifold = polygon_circle_contact_manifold(&floor, &ball).unwrap();
+        assert_similar!(manifold.normal, Vector::new(0.6, 0.8));
+        assert_similar!(manifold.points[0].depth, 0.0);
+        assert_eq!(manifold.points[0].id.a, Feature::Vertex(2));
+    }
+
+    #[test]
+    fn touching_balls() {
+        let a = Circle::new(Point::new(0.0, 0.0), 1.0);
+        let b = Circle::new(Point::new(0.0, 1.9), 1.0);
+        let manifold = circles_contact_manifold(&a, &b).unwrap();
+        assert_similar!(manifold.normal, Vector::new(0.0, 1.0));
+        assert_similar!(manifold.points[0].point, Point::new(0.0, 0.9));
+        assert_similar!(manifold.points[0].depth, 0.1);
+        let c = Circle::new(Point::new(0.0, 2.1), 1.0);
+        assert_eq!(circles_contact_manifold(&a, &c), None);
+    }
+}
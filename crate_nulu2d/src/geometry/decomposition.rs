@@ -0,0 +1,282 @@
+use crate::geometry::polygon::signed_area;
+use crate::Point;
+
+// Triangulates a simple polygon by ear clipping. Triangles are returned as
+// indices into `vertices`, sorted counter-clockwise. Collinear (and repeated)
+// vertices are dropped, so no triangle has zero area.
+pub(crate) fn ear_clip(vertices: &[Point]) -> Vec<[usize; 3]> {
+    let mut remaining: Vec<usize> = (0..vertices.len()).collect();
+    if signed_area(vertices) < 0.0 {
+        remaining.reverse();
+    }
+
+    let mut triangles = Vec::with_capacity(vertices.len().saturating_sub(2));
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        if let Some(i) = (0..len).find(|i| is_collinear(vertices, &remaining, *i)) {
+            remaining.remove(i);
+            continue;
+        }
+        let ear = (0..len)
+            .find(|i| is_ear(vertices, &remaining, *i))
+            // Numerical trouble, clip the sharpest convex vertex to keep going
+            .unwrap_or_else(|| {
+                (0..len)
+                    .max_by(|i, j| {
+                        turn(vertices, &remaining, *i)
+                            .partial_cmp(&turn(vertices, &remaining, *j))
+                            .unwrap()
+                    })
+                    .unwrap()
+            });
+        triangles.push([
+            remaining[(ear + len - 1) % len],
+            remaining[ear],
+            remaining[(ear + 1) % len],
+        ]);
+        remaining.remove(ear);
+    }
+    if remaining.len() == 3 && !is_collinear(vertices, &remaining, 0) {
+        triangles.push([remaining[0], remaining[1], remaining[2]]);
+    }
+    return triangles;
+}
+
+// Splits a simple polygon into convex pieces (Hertel-Mehlhorn): the polygon is
+// triangulated, and then neighbouring pieces are merged as long as the result
+// stays convex. Pieces are sorted counter-clockwise.
+pub(crate) fn convex_pieces(vertices: &[Point]) -> Vec<Vec<usize>> {
+    let mut pieces: Vec<Vec<usize>> = ear_clip(vertices).iter().map(|t| t.to_vec()).collect();
+
+    let mut merged = true;
+    while merged {
+        merged = false;
+        'search: for i in 0..pieces.len() {
+            for j in i + 1..pieces.len() {
+                if let Some(piece) = merge(vertices, &pieces[i], &pieces[j]) {
+                    pieces[i] = piece;
+                    pieces.remove(j);
+                    merged = true;
+                    break 'search;
+                }
+            }
+        }
+    }
+    return pieces;
+}
+
+// Vertices of the convex hull of the points, sorted counter-clockwise
+// (Andrew's monotone chain). Collinear points are dropped, so there are fewer
+// than three vertices when all the points lie on a line.
+pub(crate) fn monotone_chain(points: &[Point]) -> Vec<Point> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|p, q| {
+        p.x.partial_cmp(&q.x)
+            .unwrap()
+            .then(p.y.partial_cmp(&q.y).unwrap())
+    });
+    sorted.dedup();
+    if sorted.len() < 2 {
+        return sorted;
+    }
+
+    // Keeps only left turns
+    let half_hull = |points: &mut dyn Iterator<Item = &Point>| {
+        let mut hull: Vec<Point> = Vec::new();
+        for p in points {
+            while hull.len() >= 2
+                && (hull[hull.len() - 1] - hull[hull.len() - 2]) ^ (*p - hull[hull.len() - 1])
+                    <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(*p);
+        }
+        hull.pop();
+        return hull;
+    };
+    let mut hull = half_hull(&mut sorted.iter());
+    hull.extend(half_hull(&mut sorted.iter().rev()));
+    return hull;
+}
+
+// Joins two counter-clockwise pieces through the diagonal they share, if the
+// result is convex
+fn merge(vertices: &[Point], a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    let (la, lb) = (a.len(), b.len());
+    for i in 0..la {
+        let (u, v) = (a[i], a[(i + 1) % la]);
+        if let Some(j) = (0..lb).find(|j| b[*j] == v && b[(*j + 1) % lb] == u) {
+            // a from v around to u, then b from u around to v, without repeating them
+            let mut piece: Vec<usize> = (0..la).map(|k| a[(i + 1 + k) % la]).collect();
+            piece.extend((1..lb - 1).map(|k| b[(j + 1 + k) % lb]));
+            let convex = (0..piece.len()).all(|k| turn(vertices, &piece, k) >= -1e-12);
+            return if convex { Some(piece) } else { None };
+        }
+    }
+    return None;
+}
+
+// Cross product at the i-th vertex of the loop: positive for left turns
+fn turn(vertices: &[Point], indices: &[usize], i: usize) -> f64 {
+    let len = indices.len();
+    let prev = vertices[indices[(i + len - 1) % len]];
+    let cur = vertices[indices[i]];
+    let next = vertices[indices[(i + 1) % len]];
+    return (cur - prev) ^ (next - cur);
+}
+
+fn is_collinear(vertices: &[Point], indices: &[usize], i: usize) -> bool {
+    let len = indices.len();
+    let prev = vertices[indices[(i + len - 1) % len]];
+    let cur = vertices[indices[i]];
+    let next = vertices[indices[(i + 1) % len]];
+    return turn(vertices, indices, i).abs() <= 1e-12 * (cur - prev).norm() * (next - cur).norm();
+}
+
+fn is_ear(vertices: &[Point], indices: &[usize], i: usize) -> bool {
+    if turn(vertices, indices, i) <= 0.0 {
+        return false;
+    }
+    let len = indices.len();
+    let (a, b, c) = (
+        vertices[indices[(i + len - 1) % len]],
+        vertices[indices[i]],
+        vertices[indices[(i + 1) % len]],
+    );
+    return indices.iter().all(|k| {
+        let p = vertices[*k];
+        p == a || p == b || p == c || !in_triangle(p, a, b, c)
+    });
+}
+
+// Whether p lies inside (or on the border of) the counter-clockwise triangle abc
+fn in_triangle(p: Point, a: Point, b: Point, c: Point) -> bool {
+    return ((b - a) ^ (p - a)) >= 0.0 && ((c - b) ^ (p - b)) >= 0.0 && ((a - c) ^ (p - c)) >= 0.0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Polygon;
+    use similar::assert_similar;
+    use similar::Similar;
+
+    fn assert_convex_cover(concave: &Polygon, pieces: &[Polygon]) {
+        for piece in pieces {
+            assert!(Polygon::try_new(&piece.vertices).is_ok(), "{:?}", piece);
+        }
+        let area: f64 = pieces.iter().map(|p| p.area()).sum();
+        assert_similar!(area, concave.area());
+    }
+
+    #[test]
+    fn convex_stays_whole() {
+        let square = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 1.0),
+        ]);
+        let pieces = square.convex_decomposition();
+        assert_eq!(pieces.len(), 1);
+        assert_similar!(&pieces[0], &square);
+    }
+
+    #[test]
+    fn l_shape() {
+        let l = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(3.0, 0.0),
+            Point::new(3.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 3.0),
+            Point::new(0.0, 3.0),
+        ]);
+        let pieces = l.convex_decomposition();
+        assert_eq!(pieces.len(), 2);
+        assert_convex_cover(&l, &pieces);
+    }
+
+    #[test]
+    fn notched_ramp_clockwise() {
+        let ramp = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(0.0, 1.0),
+            Point::new(4.0, 3.0),
+            Point::new(5.0, 3.0),
+            Point::new(5.0, 2.0),
+            Point::new(6.0, 2.0),
+            Point::new(6.0, 0.0),
+        ]);
+        let pieces = ramp.convex_decomposition();
+        assert!(pieces.len() <= 3);
+        assert_convex_cover(&ramp, &pieces);
+    }
+
+    #[test]
+    fn comb() {
+        let comb = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(5.0, 0.0),
+            Point::new(5.0, 2.0),
+            Point::new(4.0, 2.0),
+            Point::new(4.0, 1.0),
+            Point::new(3.0, 1.0),
+            Point::new(3.0, 2.0),
+            Point::new(2.0, 2.0),
+            Point::new(2.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 2.0),
+            Point::new(0.0, 2.0),
+        ]);
+        let pieces = comb.convex_decomposition();
+        assert!(pieces.len() <= 7);
+        assert_convex_cover(&comb, &pieces);
+    }
+
+    #[test]
+    fn collinear_vertices() {
+        let square = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 2.0),
+            Point::new(2.0, 2.0),
+            Point::new(1.0, 2.0),
+            Point::new(0.0, 2.0),
+            Point::new(0.0, 1.0),
+        ]);
+        let pieces = square.convex_decomposition();
+        assert_eq!(pieces.len(), 1);
+        assert_eq!(pieces[0].vertices.len(), 4);
+    }
+
+    #[test]
+    fn ear_clip_triangle_count() {
+        let vertices = vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 2.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 2.0),
+        ];
+        // (2, 0), (1, 1) and (0, 2) end up collinear
+        assert_eq!(ear_clip(&vertices).len(), 2);
+    }
+
+    #[test]
+    fn monotone_chain_degenerate() {
+        let point = Point::new(1.0, 2.0);
+        assert_eq!(monotone_chain(&[point, point]), vec![point]);
+        let line = [
+            Point::new(2.0, 2.0),
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+        ];
+        assert_eq!(
+            monotone_chain(&line),
+            vec![Point::new(0.0, 0.0), Point::new(2.0, 2.0)]
+        );
+    }
+}
@@ -0,0 +1,412 @@
+use crate::geometry::collision::parametric_intersection;
+use crate::geometry::contact::CONTACT_MARGIN;
+use crate::geometry::rounded::contact_along;
+use crate::geometry::rounded::rounded_penetration;
+use crate::Aabb;
+use crate::ContactManifold;
+use crate::MassData;
+use crate::Penetration;
+use crate::Point;
+use crate::Ray;
+use crate::RayHit;
+use crate::Segment;
+use crate::Vector;
+
+use similar::Similar;
+use similar_derive::Similar;
+
+/// A single segment of static terrain.
+/// A one-sided edge only collides with shapes on the side its `normal` points
+/// to, and lets them through from the other side.
+/// Ghost vertices are the neighbours of the edge along a chain: contacts
+/// against the ends of the edge are smoothed with them, so that shapes
+/// sliding along a chain don't catch on its internal vertices.
+#[derive(Copy, Clone, Debug, PartialEq, Similar)]
+pub struct Edge {
+    pub segment: Segment,
+    pub normal: Option<Vector>,
+    pub ghost_before: Option<Point>,
+    pub ghost_after: Option<Point>,
+}
+
+/// A polyline (or a closed loop) of edges, for terrain outlines.
+/// One-sided chains collide on the right side of each edge, which is the
+/// outside of counter-clockwise loops.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chain {
+    pub vertices: Vec<Point>,
+    pub is_loop: bool,
+    pub one_sided: bool,
+}
+
+impl Edge {
+    // Collides on both sides
+    pub fn new(segment: Segment) -> Edge {
+        Edge {
+            segment,
+            normal: None,
+            ghost_before: None,
+            ghost_after: None,
+        }
+    }
+
+    // Collides on the right side of `segment.a -> segment.b`
+    pub fn one_sided(segment: Segment) -> Edge {
+        Edge {
+            segment,
+            normal: Some(segment.direction().perp().unit()),
+            ghost_before: None,
+            ghost_after: None,
+        }
+    }
+
+    // Accessors
+    pub fn aabb(&self) -> Aabb {
+        return Aabb::from_points(&[self.segment.a, self.segment.b]);
+    }
+
+    pub fn width(&self) -> f64 {
+        return (self.segment.b.x - self.segment.a.x).abs();
+    }
+
+    pub fn height(&self) -> f64 {
+        return (self.segment.b.y - self.segment.a.y).abs();
+    }
+
+    pub fn center(&self) -> Point {
+        return (self.segment.a + self.segment.b) / 2.0;
+    }
+
+    pub fn top(&self) -> f64 {
+        return f64::max(self.segment.a.y, self.segment.b.y);
+    }
+
+    pub fn bottom(&self) -> f64 {
+        return f64::min(self.segment.a.y, self.segment.b.y);
+    }
+
+    pub fn left(&self) -> f64 {
+        return f64::min(self.segment.a.x, self.segment.b.x);
+    }
+
+    pub fn right(&self) -> f64 {
+        return f64::max(self.segment.a.x, self.segment.b.x);
+    }
+
+    // Edges have no area, so bodies made of them are always static, with
+    // infinite mass and inertia whatever the density
+    pub fn area(&self) -> f64 {
+        return 0.0;
+    }
+
+    pub fn moment_of_inertia(&self, _density: f64) -> f64 {
+        return f64::INFINITY;
+    }
+
+    pub fn mass_data(&self, density: f64) -> MassData {
+        return MassData {
+            mass: f64::INFINITY,
+            center: self.center(),
+            inertia: self.moment_of_inertia(density),
+        };
+    }
+
+    // One-sided edges can only be hit from their front
+    pub fn raycast(&self, ray: &Ray, max_distance: f64) -> Option<RayHit> {
+        let path = Segment::from_arrow(ray.origin, ray.direction * max_distance);
+        let (t, s) = parametric_intersection(path, self.segment)?;
+        if !(0.0..=1.0).contains(&t) || !(0.0..=1.0).contains(&s) {
+            return None;
+        }
+        let front = self.segment.direction().perp().unit();
+        let normal = match self.normal {
+            Some(normal) if ray.direction * normal >= 0.0 => return None,
+            Some(normal) => normal,
+            None if ray.direction * front > 0.0 => front * -1.0,
+            None => front,
+        };
+        return Some(RayHit {
+            distance: t * max_distance,
+            point: ray.point_at(t * max_distance),
+            normal,
+        });
+    }
+
+    // Mutators
+    pub fn move_xy(&mut self, offset: Vector) {
+        self.segment.a += offset;
+        self.segment.b += offset;
+        self.ghost_before = self.ghost_before.map(|g| g + offset);
+        self.ghost_after = self.ghost_after.map(|g| g + offset);
+    }
+    pub fn move_x(&mut self, offset_x: f64) {
+        self.move_xy(Vector::new(offset_x, 0.0));
+    }
+    pub fn move_y(&mut self, offset_y: f64) {
+        self.move_xy(Vector::new(0.0, offset_y));
+    }
+    pub fn rotate_about(&mut self, center: Point, angle: f64) {
+        let rotate = |p: Point| center + (p - center).rotated_by(angle);
+        self.segment.a = rotate(self.segment.a);
+        self.segment.b = rotate(self.segment.b);
+        self.normal = self.normal.map(|n| n.rotated_by(angle));
+        self.ghost_before = self.ghost_before.map(rotate);
+        self.ghost_after = self.ghost_after.map(rotate);
+    }
+
+    // The normal a contact with a shape whose centroid is at `centroid` should
+    // use, given the normal computed from the bare segment. Normals from the
+    // rounded ends are only kept where the chain bends away from the shape,
+    // and otherwise replaced by the normal of the edge. Returns `None` when
+    // the contact belongs to a neighbouring edge, or to the back of a
+    // one-sided edge.
+    pub(crate) fn contact_normal(&self, normal: Vector, centroid: Point) -> Option<Vector> {
+        let direction = self.segment.direction();
+        let front = direction.perp().unit();
+        // Whether the front is on the right (1) or on the left (-1)
+        let side = match self.normal {
+            Some(n) if (centroid - self.segment.a) * n < 0.0 => return None,
+            Some(n) if n * front >= 0.0 => 1.0,
+            Some(_) => -1.0,
+            None if (centroid - self.segment.a) * front >= 0.0 => 1.0,
+            None => -1.0,
+        };
+        let face = front * side;
+        if normal * face >= 1.0 - 1e-9 {
+            return Some(face);
+        }
+
+        let corner = if normal * direction < 0.0 {
+            self.ghost_before.map(|g| (self.segment.a - g, direction))
+        } else {
+            self.ghost_after.map(|g| (direction, g - self.segment.b))
+        };
+        let (incoming, outgoing) = match corner {
+            // A free end is rounded all around its front
+            None if normal * face >= 0.0 => return Some(normal),
+            None => return None,
+            Some(corner) => corner,
+        };
+        let convex = (incoming ^ outgoing) * side > 0.0;
+        if !convex {
+            return Some(face);
+        }
+        let normal_in = incoming.perp().unit() * side;
+        let normal_out = outgoing.perp().unit() * side;
+        if is_between(normal_in, normal, normal_out) {
+            return Some(normal);
+        }
+        // Past the normal of the neighbour, which deals with it
+        return None;
+    }
+}
+
+impl Chain {
+    pub fn new(vertices: &[Point], one_sided: bool) -> Chain {
+        Chain {
+            vertices: vertices.to_vec(),
+            is_loop: false,
+            one_sided,
+        }
+    }
+
+    pub fn new_loop(vertices: &[Point], one_sided: bool) -> Chain {
+        Chain {
+            vertices: vertices.to_vec(),
+            is_loop: true,
+            one_sided,
+        }
+    }
+
+    // Each edge knows its neighbours as ghost vertices
+    pub fn edges(&self) -> Vec<Edge> {
+        let len = self.vertices.len();
+        let count = if self.is_loop {
+            len
+        } else {
+            len.saturating_sub(1)
+        };
+        let vertex = |i: isize| -> Option<Point> {
+            if self.is_loop {
+                return Some(self.vertices[i.rem_euclid(len as isize) as usize]);
+            }
+            if i < 0 || i >= len as isize {
+                return None;
+            }
+            return Some(self.vertices[i as usize]);
+        };
+        return (0..count as isize)
+            .map(|i| {
+                let segment = Segment::from_points(vertex(i).unwrap(), vertex(i + 1).unwrap());
+                let mut edge = if self.one_sided {
+                    Edge::one_sided(segment)
+                } else {
+                    Edge::new(segment)
+                };
+                edge.ghost_before = vertex(i - 1);
+                edge.ghost_after = vertex(i + 2);
+                edge
+            })
+            .collect();
+    }
+}
+
+// Whether `normal` lies on the shorter arc going from `from` to `to`
+fn is_between(from: Vector, normal: Vector, to: Vector) -> bool {
+    let turn = from ^ to;
+    return (from ^ normal) * turn >= 0.0
+        && (normal ^ to) * turn >= 0.0
+        && normal * (from + to) > 0.0;
+}
+
+// Penetration of an edge and a rounded shape, with the normal pointing
+// towards the shape
+pub(crate) fn edge_penetration(
+    edge: &Edge,
+    core: &[Point],
+    radius: f64,
+    centroid: Point,
+) -> Option<Penetration> {
+    let segment = [edge.segment.a, edge.segment.b];
+    let penetration = rounded_penetration(&segment, 0.0, core, radius)?;
+    let normal = edge.contact_normal(penetration.normal, centroid)?;
+    if normal == penetration.normal {
+        return Some(penetration);
+    }
+    // Pushed along the normal of the edge instead
+    let depth = radius
+        - core
+            .iter()
+            .map(|v| (*v - edge.segment.a) * normal)
+            .fold(f64::INFINITY, f64::min);
+    if depth < 0.0 {
+        return None;
+    }
+    return Some(Penetration { depth, normal });
+}
+
+pub(crate) fn edge_contact_manifold(
+    edge: &Edge,
+    core: &[Point],
+    radius: f64,
+    centroid: Point,
+) -> Option<ContactManifold> {
+    let penetration = edge_penetration(edge, core, radius + CONTACT_MARGIN, centroid)?;
+    return Some(contact_along(
+        &[edge.segment.a, edge.segment.b],
+        0.0,
+        core,
+        radius,
+        Penetration {
+            depth: penetration.depth - CONTACT_MARGIN,
+            normal: penetration.normal,
+        },
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::square;
+    use similar::assert_similar;
+
+    // Flat ground from right to left, so that the right side is up
+    fn ground() -> Chain {
+        Chain::new(
+            &[
+                Point::new(2.0, 0.0),
+                Point::new(1.0, 0.0),
+                Point::new(0.0, 0.0),
+                Point::new(-1.0, 0.0),
+            ],
+            true,
+        )
+    }
+
+    #[test]
+    fn chain_ghost_vertices() {
+        let edges = ground().edges();
+        assert_eq!(edges.len(), 3);
+        assert_eq!(edges[0].ghost_before, None);
+        assert_eq!(edges[0].ghost_after, Some(Point::new(0.0, 0.0)));
+        assert_eq!(edges[1].ghost_before, Some(Point::new(2.0, 0.0)));
+        assert_eq!(edges[2].ghost_after, None);
+        assert_similar!(edges[1].normal.unwrap(), Vector::new(0.0, 1.0));
+
+        let ring = Chain::new_loop(&square(0.0, 0.0, 1.0).vertices, true).edges();
+        assert_eq!(ring.len(), 4);
+        assert_eq!(ring[0].ghost_before, Some(Point::new(0.0, 1.0)));
+        assert_eq!(ring[3].ghost_after, Some(Point::new(1.0, 0.0)));
+        assert_similar!(ring[0].normal.unwrap(), Vector::new(0.0, -1.0));
+    }
+
+    #[test]
+    fn one_sided_lets_shapes_through_from_behind() {
+        let edge = ground().edges()[1];
+        let above = square(0.25, -0.1, 0.5).vertices;
+        let below = square(0.25, -0.4, 0.5).vertices;
+        let penetration = edge_penetration(&edge, &above, 0.0, Point::new(0.5, 0.15)).unwrap();
+        assert_similar!(penetration.normal, Vector::new(0.0, 1.0));
+        assert_similar!(penetration.depth, 0.1);
+        assert_eq!(
+            edge_penetration(&edge, &below, 0.0, Point::new(0.5, -0.15)),
+            None
+        );
+
+        let two_sided = Edge::new(edge.segment);
+        let penetration =
+            edge_penetration(&two_sided, &below, 0.0, Point::new(0.5, -0.15)).unwrap();
+        assert_similar!(penetration.normal, Vector::new(0.0, -1.0));
+    }
+
+    #[test]
+    fn internal_vertex_does_not_catch() {
+        // A box that sank a little while sliding left, and reaches the next edge
+        let edge = ground().edges()[1];
+        let crate_ = square(1.0 - 1e-3, -1e-2, 1.0).vertices;
+        let lone = Edge::one_sided(edge.segment);
+        let caught = edge_penetration(&lone, &crate_, 0.0, Point::new(1.5, 0.5)).unwrap();
+        assert_similar!(caught.normal, Vector::new(1.0, 0.0));
+
+        let smooth = edge_penetration(&edge, &crate_, 0.0, Point::new(1.5, 0.5)).unwrap();
+        assert_similar!(smooth.normal, Vector::new(0.0, 1.0));
+        assert_similar!(smooth.depth, 1e-2);
+    }
+
+    #[test]
+    fn convex_corner_keeps_rounded_normal() {
+        // A ridge: up from the left, then down to the right
+        let ridge = Chain::new(
+            &[
+                Point::new(2.0, 0.0),
+                Point::new(1.0, 1.0),
+                Point::new(0.0, 0.0),
+            ],
+            true,
+        );
+        let edges = ridge.edges();
+        let ball = [Point::new(1.0, 1.4)];
+        let penetration = edge_penetration(&edges[0], &ball, 0.5, ball[0]).unwrap();
+        assert_similar!(penetration.normal, Vector::new(0.0, 1.0));
+        assert_similar!(penetration.depth, 0.1);
+    }
+
+    #[test]
+    fn raycast_one_sided() {
+        let edge = ground().edges()[1];
+        let down = Ray::new(Point::new(0.5, 2.0), Vector::new(0.0, -1.0));
+        let expected = RayHit {
+            distance: 2.0,
+            point: Point::new(0.5, 0.0),
+            normal: Vector::new(0.0, 1.0),
+        };
+        assert_similar!(edge.raycast(&down, 5.0), Some(expected));
+        let up = Ray::new(Point::new(0.5, -2.0), Vector::new(0.0, 1.0));
+        assert_similar!(edge.raycast(&up, 5.0), Option::<RayHit>::None);
+        let two_sided = Edge::new(edge.segment);
+        assert_similar!(
+            two_sided.raycast(&up, 5.0).unwrap().normal,
+            Vector::new(0.0, -1.0)
+        );
+    }
+}
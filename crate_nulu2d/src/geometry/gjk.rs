@@ -0,0 +1,373 @@
+//! Distance and penetration between convex shapes, only known through their
+//! support function.
+//!
+//! GJK looks for the point of the Minkowski difference `A - B` closest to the
+//! origin: its length is the distance between the shapes, and the origin
+//! being inside means they overlap. EPA then grows a polygon inside `A - B`
+//! towards its boundary, until it finds the face closest to the origin,
+//! which gives the penetration depth and normal.
+
+use crate::Penetration;
+use crate::Point;
+use crate::SupportMap;
+use crate::Vector;
+
+use similar::Similar;
+use similar_derive::Similar;
+
+const MAX_ITERATIONS: usize = 64;
+const TOLERANCE: f64 = 1e-10;
+
+/// The closest points of two separated shapes, and the distance between them.
+#[derive(Copy, Clone, Debug, PartialEq, Similar)]
+pub struct ClosestPoints {
+    pub distance: f64,
+    pub point_a: Point,
+    pub point_b: Point,
+}
+
+// A point of the Minkowski difference, with the points of each shape it
+// comes from
+#[derive(Copy, Clone, Debug)]
+struct SimplexVertex {
+    a: Point,
+    b: Point,
+    w: Vector,
+}
+
+// Barycentric weight of each vertex of the simplex
+type Simplex = Vec<(SimplexVertex, f64)>;
+
+fn support<A, B>(a: &A, b: &B, direction: Vector) -> SimplexVertex
+where
+    A: SupportMap + ?Sized,
+    B: SupportMap + ?Sized,
+{
+    let (pa, pb) = (a.support(direction), b.support(direction * -1.0));
+    return SimplexVertex {
+        a: pa,
+        b: pb,
+        w: pa - pb,
+    };
+}
+
+fn closest(simplex: &Simplex) -> Vector {
+    return simplex
+        .iter()
+        .fold(Vector::zero(), |v, (vertex, weight)| v + vertex.w * *weight);
+}
+
+// Closest point of a segment or triangle to the origin, keeping only the
+// vertices of the feature it lies on (see Box2D's b2Simplex::Solve2/Solve3)
+fn reduce(vertices: &[SimplexVertex]) -> Simplex {
+    match vertices {
+        [v1, v2] => {
+            let e12 = v2.w - v1.w;
+            let d12_2 = -(v1.w * e12);
+            if d12_2 <= 0.0 {
+                return vec![(*v1, 1.0)];
+            }
+            let d12_1 = v2.w * e12;
+            if d12_1 <= 0.0 {
+                return vec![(*v2, 1.0)];
+            }
+            let total = d12_1 + d12_2;
+            return vec![(*v1, d12_1 / total), (*v2, d12_2 / total)];
+        }
+        [v1, v2, v3] => {
+            let (w1, w2, w3) = (v1.w, v2.w, v3.w);
+            let (e12, e13, e23) = (w2 - w1, w3 - w1, w3 - w2);
+            let (d12_1, d12_2) = (w2 * e12, -(w1 * e12));
+            let (d13_1, d13_2) = (w3 * e13, -(w1 * e13));
+            let (d23_1, d23_2) = (w3 * e23, -(w2 * e23));
+            let n123 = e12 ^ e13;
+            let d123_1 = n123 * (w2 ^ w3);
+            let d123_2 = n123 * (w3 ^ w1);
+            let d123_3 = n123 * (w1 ^ w2);
+
+            let edge = |p: &SimplexVertex, q: &SimplexVertex, dp: f64, dq: f64| {
+                vec![(*p, dp / (dp + dq)), (*q, dq / (dp + dq))]
+            };
+            if d12_2 <= 0.0 && d13_2 <= 0.0 {
+                return vec![(*v1, 1.0)];
+            }
+            if d12_1 > 0.0 && d12_2 > 0.0 && d123_3 <= 0.0 {
+                return edge(v1, v2, d12_1, d12_2);
+            }
+            if d13_1 > 0.0 && d13_2 > 0.0 && d123_2 <= 0.0 {
+                return edge(v1, v3, d13_1, d13_2);
+            }
+            if d12_1 <= 0.0 && d23_2 <= 0.0 {
+                return vec![(*v2, 1.0)];
+            }
+            if d13_1 <= 0.0 && d23_1 <= 0.0 {
+                return vec![(*v3, 1.0)];
+            }
+            if d23_1 > 0.0 && d23_2 > 0.0 && d123_1 <= 0.0 {
+                return edge(v2, v3, d23_1, d23_2);
+            }
+            // The origin is inside the triangle
+            let total = d123_1 + d123_2 + d123_3;
+            return vec![
+                (*v1, d123_1 / total),
+                (*v2, d123_2 / total),
+                (*v3, d123_3 / total),
+            ];
+        }
+        _ => return vertices.iter().map(|v| (*v, 1.0)).collect(),
+    }
+}
+
+// Runs GJK until the simplex surrounds the origin (three vertices are
+// returned) or reaches it, or no vertex gets any closer
+fn gjk<A, B>(a: &A, b: &B) -> Simplex
+where
+    A: SupportMap + ?Sized,
+    B: SupportMap + ?Sized,
+{
+    let mut simplex = vec![(support(a, b, Vector::new(1.0, 0.0)), 1.0)];
+    for _ in 0..MAX_ITERATIONS {
+        let v = closest(&simplex);
+        if simplex.len() == 3 || v.norm() < TOLERANCE {
+            return simplex;
+        }
+        let new = support(a, b, v * -1.0);
+        let repeated = simplex.iter().any(|(vertex, _)| vertex.w == new.w);
+        if repeated || v * v - v * new.w <= TOLERANCE * (v * v) {
+            return simplex;
+        }
+        let mut vertices: Vec<SimplexVertex> = simplex.iter().map(|(vertex, _)| *vertex).collect();
+        vertices.push(new);
+        simplex = reduce(&vertices);
+    }
+    return simplex;
+}
+
+/// Returns the distance and closest points of two convex shapes, or `None`
+/// if they overlap. Touching shapes count as overlapping.
+/// On curved shapes the points are only accurate to about `1e-5`.
+pub fn gjk_distance<A, B>(a: &A, b: &B) -> Option<ClosestPoints>
+where
+    A: SupportMap + ?Sized,
+    B: SupportMap + ?Sized,
+{
+    let simplex = gjk(a, b);
+    let v = closest(&simplex);
+    if simplex.len() == 3 || v.norm() < TOLERANCE {
+        return None;
+    }
+    let point_a = simplex
+        .iter()
+        .fold(Point::zero(), |p, (vertex, weight)| p + vertex.a * *weight);
+    let point_b = simplex
+        .iter()
+        .fold(Point::zero(), |p, (vertex, weight)| p + vertex.b * *weight);
+    return Some(ClosestPoints {
+        distance: v.norm(),
+        point_a,
+        point_b,
+    });
+}
+
+/// Returns whether two convex shapes overlap. Touching borders count as
+/// overlapping.
+pub fn gjk_overlaps<A, B>(a: &A, b: &B) -> bool
+where
+    A: SupportMap + ?Sized,
+    B: SupportMap + ?Sized,
+{
+    if !a.aabb().overlaps(&b.aabb()) {
+        return false;
+    }
+    return gjk_distance(a, b).is_none();
+}
+
+/// Returns the penetration of two overlapping convex shapes, with the normal
+/// pointing from `a` towards `b`, or `None` if they don't overlap.
+/// Curved shapes are approximated by a polygon of at most 64 more vertices,
+/// so their depth is slightly underestimated.
+pub fn epa_penetration<A, B>(a: &A, b: &B) -> Option<Penetration>
+where
+    A: SupportMap + ?Sized,
+    B: SupportMap + ?Sized,
+{
+    let simplex = gjk(a, b);
+    if simplex.len() < 3 && closest(&simplex).norm() >= TOLERANCE {
+        return None;
+    }
+
+    // Touching shapes leave the origin on a vertex or an edge of the
+    // simplex, which has to be completed into a triangle first
+    let mut polytope: Vec<Vector> = simplex.iter().map(|(vertex, _)| vertex.w).collect();
+    for direction in [Vector::new(1.0, 0.0), Vector::new(0.0, 1.0)].iter() {
+        if polytope.len() == 1 {
+            let w = support(a, b, *direction).w;
+            if w != polytope[0] {
+                polytope.push(w);
+            }
+        }
+    }
+    if polytope.len() == 1 {
+        // `A - B` is a single point, e.g. for two coincident points
+        return Some(Penetration {
+            depth: 0.0,
+            normal: Vector::new(0.0, 1.0),
+        });
+    }
+    if polytope.len() == 2 {
+        let side = (polytope[1] - polytope[0]).perp();
+        let (w1, w2) = (support(a, b, side).w, support(a, b, side * -1.0).w);
+        let distance = |w: Vector| ((w - polytope[0]) * side).abs();
+        polytope.push(if distance(w1) >= distance(w2) { w1 } else { w2 });
+    }
+    let area = (polytope[1] - polytope[0]) ^ (polytope[2] - polytope[0]);
+    if area.abs() < TOLERANCE {
+        // `A - B` has no area, e.g. for two parallel edges
+        let normal = (polytope[1] - polytope[0]).perp().unit();
+        return Some(Penetration { depth: 0.0, normal });
+    }
+    if area < 0.0 {
+        polytope.swap(1, 2);
+    }
+
+    // The outward normal of the closest face of `A - B` is the direction in
+    // which `b` has to move to get out of `a`
+    let mut face = (0, 0.0, Vector::zero());
+    for _ in 0..MAX_ITERATIONS {
+        face = closest_face(&polytope);
+        let (index, distance, normal) = face;
+        let w = support(a, b, normal).w;
+        if w * normal - distance <= TOLERANCE {
+            break;
+        }
+        polytope.insert(index + 1, w);
+    }
+    let (_, distance, normal) = face;
+    return Some(Penetration {
+        depth: f64::max(distance, 0.0),
+        normal,
+    });
+}
+
+// Index of the first vertex, distance to the origin and outward normal of
+// the face of a counter-clockwise polygon closest to the origin
+fn closest_face(polytope: &[Vector]) -> (usize, f64, Vector) {
+    let len = polytope.len();
+    return (0..len)
+        .filter_map(|i| {
+            let edge = polytope[(i + 1) % len] - polytope[i];
+            if edge.norm() == 0.0 {
+                return None;
+            }
+            let normal = edge.perp().unit();
+            return Some((i, polytope[i] * normal, normal));
+        })
+        .min_by(|(_, d1, _), (_, d2, _)| d1.partial_cmp(d2).unwrap())
+        .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::square;
+    use crate::geometry::collision::circles_penetration;
+    use crate::geometry::collision::penetration;
+    use crate::Capsule;
+    use crate::Circle;
+    use crate::Segment;
+    use similar::assert_similar;
+
+    #[test]
+    fn distance_between_squares() {
+        let a = square(0.0, 0.0, 1.0);
+        let b = square(3.0, 0.5, 1.0);
+        let closest = gjk_distance(&a, &b).unwrap();
+        assert_similar!(closest.distance, 2.0);
+        assert_similar!(closest.point_a.x, 1.0);
+        assert_similar!(closest.point_b.x, 3.0);
+        assert_similar!(closest.point_a.y, closest.point_b.y);
+
+        // Corner to corner
+        let c = square(2.0, 2.0, 1.0);
+        let closest = gjk_distance(&a, &c).unwrap();
+        assert_similar!(closest.distance, 2.0_f64.sqrt());
+        assert_similar!(closest.point_a, Point::new(1.0, 1.0));
+        assert_similar!(closest.point_b, Point::new(2.0, 2.0));
+    }
+
+    #[test]
+    fn distance_to_curved_shapes() {
+        let a = square(0.0, 0.0, 1.0);
+        let circle = Circle::new(Point::new(4.0, 5.0), 1.0);
+        let closest = gjk_distance(&a, &circle).unwrap();
+        assert_similar!(closest.distance, 4.0, 1e-6);
+        // The closest point on a curve converges slower than the distance
+        assert_similar!(closest.point_a, Point::new(1.0, 1.0), 1e-6);
+        assert_similar!(closest.point_b, Point::new(3.4, 4.2), 1e-4);
+
+        let capsule = Capsule::new(
+            Segment::from_points(Point::new(-3.0, 3.0), Point::new(3.0, 3.0)),
+            0.5,
+        );
+        let closest = gjk_distance(&capsule, &a).unwrap();
+        assert_similar!(closest.distance, 1.5, 1e-6);
+        assert_similar!(closest.point_a.y, 2.5, 1e-6);
+    }
+
+    #[test]
+    fn overlapping_and_touching() {
+        let a = square(0.0, 0.0, 1.0);
+        assert!(gjk_overlaps(&a, &square(0.5, 0.5, 1.0)));
+        assert!(gjk_overlaps(&a, &square(1.0, 0.0, 1.0)));
+        assert!(gjk_overlaps(&a, &square(0.25, 0.25, 0.5)));
+        assert!(!gjk_overlaps(&a, &square(1.1, 0.0, 1.0)));
+        assert_eq!(gjk_distance(&a, &square(0.5, 0.5, 1.0)), None);
+    }
+
+    #[test]
+    fn epa_matches_sat() {
+        let a = square(0.0, 0.0, 2.0);
+        let others = vec![
+            square(1.5, 0.5, 1.0),
+            square(-0.8, 0.2, 1.0),
+            square(0.5, 1.7, 1.0),
+            square(0.4, -0.6, 1.0),
+        ];
+        for b in others {
+            let expected = penetration(&a, &b).unwrap();
+            assert_similar!(epa_penetration(&a, &b).unwrap(), expected);
+        }
+        assert_eq!(epa_penetration(&a, &square(3.0, 0.0, 1.0)), None);
+    }
+
+    #[test]
+    fn epa_touching() {
+        let a = square(0.0, 0.0, 1.0);
+        let b = square(1.0, 0.0, 1.0);
+        let expected = Penetration {
+            depth: 0.0,
+            normal: Vector::new(1.0, 0.0),
+        };
+        assert_similar!(epa_penetration(&a, &b).unwrap(), expected);
+    }
+
+    #[test]
+    fn epa_circles() {
+        let a = Circle::new(Point::new(0.0, 0.0), 1.0);
+        let b = Circle::new(Point::new(1.2, 0.9), 1.0);
+        let expected = circles_penetration(&a, &b).unwrap();
+        let result = epa_penetration(&a, &b).unwrap();
+        assert_similar!(result.depth, expected.depth, 1e-3);
+        assert_similar!(result.normal, expected.normal, 1e-2);
+    }
+
+    #[test]
+    fn epa_coincident_points() {
+        let a = Circle::new(Point::new(1.0, 2.0), 0.0);
+        let expected = Penetration {
+            depth: 0.0,
+            normal: Vector::new(0.0, 1.0),
+        };
+        assert_similar!(epa_penetration(&a, &a).unwrap(), expected);
+    }
+}
@@ -0,0 +1,13 @@
+use crate::Point;
+
+use similar::Similar;
+use similar_derive::Similar;
+
+/// Mass properties of a shape of uniform density. The inertia is taken
+/// around the center of mass.
+#[derive(Copy, Clone, Debug, PartialEq, Similar)]
+pub struct MassData {
+    pub mass: f64,
+    pub center: Point,
+    pub inertia: f64,
+}
@@ -1,9 +1,45 @@
+pub mod aabb;
+pub mod boolean;
+pub mod capsule;
+pub mod circle;
 pub mod collision;
+pub mod contact;
+pub mod decomposition;
+pub mod edge;
+pub mod gjk;
+pub mod mass;
 pub mod point;
 pub mod polygon;
+pub mod ray;
+pub mod rounded;
+pub mod rounded_polygon;
 pub mod segment;
+pub mod shape;
+pub mod simplify;
+pub mod support_map;
+pub mod transform;
 
+pub use self::aabb::Aabb;
+pub use self::boolean::PolygonWithHoles;
+pub use self::capsule::Capsule;
+pub use self::circle::Circle;
+pub use self::collision::Penetration;
+pub use self::contact::ContactManifold;
+pub use self::contact::ContactPoint;
+pub use self::contact::Feature;
+pub use self::contact::FeatureId;
+pub use self::edge::Chain;
+pub use self::edge::Edge;
+pub use self::gjk::ClosestPoints;
+pub use self::mass::MassData;
 pub use self::point::Point;
 pub use self::point::Vector;
 pub use self::polygon::Polygon;
+pub use self::polygon::PolygonError;
+pub use self::ray::Ray;
+pub use self::ray::RayHit;
+pub use self::rounded_polygon::RoundedPolygon;
 pub use self::segment::Segment;
+pub use self::shape::Shape;
+pub use self::support_map::SupportMap;
+pub use self::transform::Transform;
@@ -45,6 +45,12 @@ impl Point {
         Point::from_polar(angle, self.norm())
     }
 
+    // Unlike `rotated`, which sets the angle, this rotates the point around the origin
+    pub fn rotated_by(self, angle: f64) -> Point {
+        let (sin, cos) = angle.sin_cos();
+        Point::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
     pub fn trimmed(self, max_length: f64) -> Point {
         Point::from_polar(self.angle(), self.norm().min(max_length))
     }
@@ -61,6 +67,12 @@ impl Point {
         Point::new(self.y, -self.x)
     }
 
+    // Splits the vector into its projection on `axis` and the remainder
+    pub fn decompose_into(self, axis: Point) -> (Point, Point) {
+        let projection = self.vector_projection_to(axis);
+        return (projection, self - projection);
+    }
+
     // Mutators
     pub fn set_angle(&mut self, angle: f64) {
         self.direct_to(angle, self.norm());
@@ -309,6 +321,15 @@ mod tests {
         assert_similar!(point.y, 0.0);
     }
 
+    #[test]
+    fn rotated_by() {
+        let point = Point::new(1.0, 1.0);
+        let rotated = point.rotated_by(consts::PI / 2.0);
+        assert_similar!(rotated, Point::new(-1.0, 1.0));
+        let rotated = point.rotated_by(-consts::PI);
+        assert_similar!(rotated, Point::new(-1.0, -1.0));
+    }
+
     #[test]
     fn scalar_projection_to() {
         let p1 = Point::new(1.0, 1.0).unit();
@@ -327,6 +348,14 @@ mod tests {
         assert_similar!(actual.y, expected.y);
     }
 
+    #[test]
+    fn decompose_into() {
+        let p = Point::new(3.0, 2.0);
+        let (into, along) = p.decompose_into(Point::new(0.0, -2.0));
+        assert_similar!(into, Point::new(0.0, 2.0));
+        assert_similar!(along, Point::new(3.0, 0.0));
+    }
+
     #[test]
     fn distance() {
         let p1 = Point::new(2.0, 1.0);
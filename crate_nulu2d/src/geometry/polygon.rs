@@ -1,26 +1,287 @@
+use crate::geometry::boolean::boolean;
+use crate::geometry::boolean::outline;
+use crate::geometry::boolean::BooleanOp;
+use crate::geometry::collision::intersection;
+use crate::geometry::collision::parametric_intersection;
+use crate::geometry::decomposition::convex_pieces;
+use crate::geometry::decomposition::ear_clip;
+use crate::geometry::decomposition::monotone_chain;
+use crate::geometry::rounded::minkowski_sum;
+use crate::geometry::simplify::ramer_douglas_peucker;
+use crate::geometry::simplify::remove_collinear;
+use crate::geometry::simplify::weld;
+use crate::Aabb;
+use crate::MassData;
 use crate::Point;
+use crate::PolygonWithHoles;
+use crate::Ray;
+use crate::RayHit;
 use crate::Segment;
 use crate::Vector;
 use similar::Similar;
+use std::error::Error;
+use std::fmt;
 
-#[derive(Debug, PartialEq)]
+// Vertices closer than this to a splitting line are on the line
+const SPLIT_EPSILON: f64 = 1e-9;
+
+// Polygons shrunk by `offset` below this area have vanished
+const OFFSET_MIN_AREA: f64 = 1e-9;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Polygon {
     pub vertices: Vec<Point>,
 }
 
+/// Reasons why a list of points isn't a valid convex polygon.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PolygonError {
+    /// Less than three vertices were given
+    TooFewVertices(usize),
+    /// The vertex at the given index repeats an earlier one
+    RepeatedVertex(usize),
+    ZeroArea,
+    SelfIntersecting,
+    NotConvex,
+}
+
+impl fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PolygonError::TooFewVertices(n) => write!(
+                f,
+                "A polygon must be initialized with 3 or more vertices ({} used)",
+                n
+            ),
+            PolygonError::RepeatedVertex(i) => write!(f, "Vertex {} is repeated", i),
+            PolygonError::ZeroArea => write!(f, "The polygon has no area"),
+            PolygonError::SelfIntersecting => write!(f, "The polygon intersects itself"),
+            PolygonError::NotConvex => write!(f, "The polygon is not convex"),
+        }
+    }
+}
+
+impl Error for PolygonError {}
+
 impl Polygon {
     // Constructors
+    // Assumes the vertices describe a convex polygon, and panics with fewer
+    // than 3 of them. Vertices from user input should go through `try_new`,
+    // which returns a `PolygonError` instead.
     pub fn new(v: &Vec<Point>) -> Polygon {
-        assert!(
-            v.len() >= 3,
-            "A polygon must be initialized with 3 or more vertices ({} used)",
-            v.len()
-        );
+        if v.len() < 3 {
+            panic!("{}", PolygonError::TooFewVertices(v.len()));
+        }
         Polygon {
             vertices: v.clone(),
         }
     }
 
+    // Checks that the vertices describe a convex polygon with some area, and
+    // sorts them counter-clockwise
+    pub fn try_new(v: &[Point]) -> Result<Polygon, PolygonError> {
+        if v.len() < 3 {
+            return Err(PolygonError::TooFewVertices(v.len()));
+        }
+        for i in 1..v.len() {
+            if v[..i].contains(&v[i]) {
+                return Err(PolygonError::RepeatedVertex(i));
+            }
+        }
+
+        let mut polygon = Polygon {
+            vertices: v.to_vec(),
+        };
+        if polygon.has_crossing_edges() {
+            return Err(PolygonError::SelfIntersecting);
+        }
+        if polygon.area() <= f64::EPSILON {
+            return Err(PolygonError::ZeroArea);
+        }
+        if polygon.doubles_back() {
+            return Err(PolygonError::SelfIntersecting);
+        }
+
+        let len = v.len();
+        let turns: Vec<f64> = (0..len)
+            .map(|i| (v[(i + 1) % len] - v[i]) ^ (v[(i + 2) % len] - v[(i + 1) % len]))
+            .collect();
+        if turns.iter().any(|t| *t > 0.0) && turns.iter().any(|t| *t < 0.0) {
+            return Err(PolygonError::NotConvex);
+        }
+
+        if polygon.signed_area() < 0.0 {
+            polygon.vertices.reverse();
+        }
+        return Ok(polygon);
+    }
+
+    // Smallest convex polygon containing all the points, with its vertices
+    // sorted counter-clockwise (Andrew's monotone chain)
+    pub fn convex_hull(points: &[Point]) -> Result<Polygon, PolygonError> {
+        if points.len() < 3 {
+            return Err(PolygonError::TooFewVertices(points.len()));
+        }
+
+        let hull = monotone_chain(points);
+        if hull.len() < 3 {
+            return Err(PolygonError::ZeroArea);
+        }
+        return Ok(Polygon { vertices: hull });
+    }
+
+    // Splits a simple (possibly concave) polygon into counter-clockwise
+    // triangles, whatever the winding of its vertices
+    pub fn triangulate(&self) -> Vec<[Point; 3]> {
+        return ear_clip(&self.vertices)
+            .into_iter()
+            .map(|[a, b, c]| [self.vertices[a], self.vertices[b], self.vertices[c]])
+            .collect();
+    }
+
+    // Splits a simple (possibly concave) polygon into convex polygons
+    pub fn convex_decomposition(&self) -> Vec<Polygon> {
+        return convex_pieces(&self.vertices)
+            .into_iter()
+            .map(|piece| Polygon {
+                vertices: piece.into_iter().map(|i| self.vertices[i]).collect(),
+            })
+            .collect();
+    }
+
+    // Convex polygon covering every sum of a point of `self` and a point of
+    // `other`, sorted counter-clockwise. Both are expected to be convex.
+    pub fn minkowski_sum(&self, other: &Polygon) -> Polygon {
+        return Polygon {
+            vertices: minkowski_sum(&self.vertices, &other.vertices),
+        };
+    }
+
+    // Moves every edge of a convex polygon outwards by `distance` (inwards if
+    // negative), keeping the corners sharp: unlike a `RoundedPolygon`, the
+    // corners end up further than `distance` from the original polygon.
+    // Returns `None` if the polygon shrinks away.
+    pub fn offset(&self, distance: f64) -> Option<Polygon> {
+        let segments = self.segments();
+        let normals = self.normals();
+        if distance >= 0.0 {
+            // Each vertex slides along the bisector of its two edges
+            let len = self.vertices.len();
+            let vertices = (0..len)
+                .map(|i| {
+                    let (before, after) = (normals[(i + len - 1) % len], normals[i]);
+                    self.vertices[i] + (before + after) * (distance / (1.0 + before * after))
+                })
+                .collect();
+            return Some(Polygon { vertices });
+        }
+
+        // Edges may vanish when shrinking, so the polygon is clipped by each
+        // moved edge instead
+        let mut vertices = self.vertices.clone();
+        for (segment, normal) in segments.into_iter().zip(normals) {
+            let outside = |p: Point| (p - segment.a) * normal - distance;
+            let mut clipped = Vec::with_capacity(vertices.len() + 1);
+            for (i, p) in vertices.iter().enumerate() {
+                let q = vertices[(i + 1) % vertices.len()];
+                let (outside_p, outside_q) = (outside(*p), outside(q));
+                if outside_p <= 0.0 {
+                    clipped.push(*p);
+                }
+                if (outside_p < 0.0 && outside_q > 0.0) || (outside_p > 0.0 && outside_q < 0.0) {
+                    clipped.push(*p + (q - *p) * (outside_p / (outside_p - outside_q)));
+                }
+            }
+            vertices = clipped;
+        }
+        let polygon = Polygon { vertices };
+        if polygon.vertices.len() < 3 || polygon.area() < OFFSET_MIN_AREA {
+            return None;
+        }
+        return Some(polygon);
+    }
+
+    // Cleanups for outlines with too many vertices (e.g. traced from
+    // images). Each returns the new polygon and how many vertices were
+    // dropped, and never goes below 3 vertices, returning the polygon as is
+    // instead.
+
+    // Merges consecutive vertices closer than `tolerance`
+    pub fn weld_vertices(&self, tolerance: f64) -> (Polygon, usize) {
+        return self.kept_vertices(weld(&self.vertices, tolerance));
+    }
+
+    // Drops the vertices within `tolerance` of the segment joining their neighbours
+    pub fn remove_collinear_vertices(&self, tolerance: f64) -> (Polygon, usize) {
+        return self.kept_vertices(remove_collinear(&self.vertices, tolerance));
+    }
+
+    // Keeps as few vertices as possible without moving the outline further
+    // than `tolerance` (Ramer-Douglas-Peucker)
+    pub fn simplify(&self, tolerance: f64) -> (Polygon, usize) {
+        return self.kept_vertices(ramer_douglas_peucker(&self.vertices, tolerance));
+    }
+
+    fn kept_vertices(&self, kept: Vec<usize>) -> (Polygon, usize) {
+        let dropped = self.vertices.len() - kept.len();
+        let vertices = kept.into_iter().map(|i| self.vertices[i]).collect();
+        return (Polygon { vertices }, dropped);
+    }
+
+    // Cuts a convex polygon along the (infinite) line through `line`, giving
+    // the pieces to its left and to its right, with the winding of `self`.
+    // Returns `None` if the line misses the polygon or only grazes it.
+    pub fn split_by_line(&self, line: Segment) -> Option<(Polygon, Polygon)> {
+        let direction = line.direction().unit();
+        let side = |p: Point| direction ^ (p - line.a);
+        let (mut left, mut right) = (Vec::new(), Vec::new());
+        for segment in self.segments() {
+            let (side_a, side_b) = (side(segment.a), side(segment.b));
+            if side_a >= -SPLIT_EPSILON {
+                left.push(segment.a);
+            }
+            if side_a <= SPLIT_EPSILON {
+                right.push(segment.a);
+            }
+            let crosses = (side_a > SPLIT_EPSILON && side_b < -SPLIT_EPSILON)
+                || (side_a < -SPLIT_EPSILON && side_b > SPLIT_EPSILON);
+            if crosses {
+                let crossing = segment.a + segment.direction() * (side_a / (side_a - side_b));
+                left.push(crossing);
+                right.push(crossing);
+            }
+        }
+
+        let left = Polygon { vertices: left };
+        let right = Polygon { vertices: right };
+        if left.vertices.len() < 3 || right.vertices.len() < 3 {
+            return None;
+        }
+        if left.area() < SPLIT_EPSILON || right.area() < SPLIT_EPSILON {
+            return None;
+        }
+        return Some((left, right));
+    }
+
+    // Boolean operations, for simple (possibly concave) polygons of any
+    // winding. Shared or overlapping edges are fine; the result may have
+    // several pieces, and holes (see `PolygonWithHoles`).
+    pub fn union(&self, other: &Polygon) -> Vec<PolygonWithHoles> {
+        return boolean(&outline(self), &outline(other), BooleanOp::Union);
+    }
+
+    pub fn intersection(&self, other: &Polygon) -> Vec<PolygonWithHoles> {
+        return boolean(&outline(self), &outline(other), BooleanOp::Intersection);
+    }
+
+    pub fn difference(&self, other: &Polygon) -> Vec<PolygonWithHoles> {
+        return boolean(&outline(self), &outline(other), BooleanOp::Difference);
+    }
+
+    pub fn xor(&self, other: &Polygon) -> Vec<PolygonWithHoles> {
+        return boolean(&outline(self), &outline(other), BooleanOp::Xor);
+    }
+
     // Accessors
     // TODO: consider caching some of these
     pub fn segments(&self) -> Vec<Segment> {
@@ -36,28 +297,21 @@ impl Polygon {
         return res;
     }
 
+    // Outward unit normal of each segment, whatever the winding of the vertices
+    pub fn normals(&self) -> Vec<Vector> {
+        return outward_normals(&self.vertices);
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        return Aabb::from_points(&self.vertices);
+    }
+
     pub fn width(&self) -> f64 {
-        let max_x = self
-            .vertices
-            .iter()
-            .fold(f64::NEG_INFINITY, |m, p| f64::max(m, p.x));
-        let min_x = self
-            .vertices
-            .iter()
-            .fold(f64::INFINITY, |m, p| f64::min(m, p.x));
-        return max_x - min_x;
+        return self.aabb().width();
     }
 
     pub fn height(&self) -> f64 {
-        let max_y = self
-            .vertices
-            .iter()
-            .fold(f64::NEG_INFINITY, |m, p| f64::max(m, p.y));
-        let min_y = self
-            .vertices
-            .iter()
-            .fold(f64::INFINITY, |m, p| f64::min(m, p.y));
-        return max_y - min_y;
+        return self.aabb().height();
     }
 
     pub fn center(&self) -> Point {
@@ -66,35 +320,19 @@ impl Polygon {
     }
 
     pub fn top(&self) -> f64 {
-        return self
-            .vertices
-            .iter()
-            .map(|p| p.y)
-            .fold(f64::NEG_INFINITY, f64::max);
+        return self.aabb().max.y;
     }
 
     pub fn bottom(&self) -> f64 {
-        return self
-            .vertices
-            .iter()
-            .map(|p| p.y)
-            .fold(f64::INFINITY, f64::min);
+        return self.aabb().min.y;
     }
 
     pub fn left(&self) -> f64 {
-        return self
-            .vertices
-            .iter()
-            .map(|p| p.x)
-            .fold(f64::INFINITY, f64::min);
+        return self.aabb().min.x;
     }
 
     pub fn right(&self) -> f64 {
-        return self
-            .vertices
-            .iter()
-            .map(|p| p.x)
-            .fold(f64::NEG_INFINITY, f64::max);
+        return self.aabb().max.x;
     }
 
     pub fn centroid(&self) -> Point {
@@ -114,6 +352,94 @@ impl Polygon {
         return centroid;
     }
 
+    pub fn area(&self) -> f64 {
+        return self.signed_area().abs();
+    }
+
+    // Around the centroid
+    pub fn moment_of_inertia(&self, density: f64) -> f64 {
+        let centroid = self.centroid();
+        let len = self.vertices.len();
+        let mut inertia = 0.0;
+        for i in 0..len {
+            let p = self.vertices[i] - centroid;
+            let q = self.vertices[(i + 1) % len] - centroid;
+            inertia += (p ^ q) * (p * p + p * q + q * q);
+        }
+        return density * (inertia / 12.0).abs();
+    }
+
+    pub fn mass_data(&self, density: f64) -> MassData {
+        return MassData {
+            mass: density * self.area(),
+            center: self.centroid(),
+            inertia: self.moment_of_inertia(density),
+        };
+    }
+
+    // Only the edges the ray enters through count, so rays starting inside
+    // the polygon return `None`
+    pub fn raycast(&self, ray: &Ray, max_distance: f64) -> Option<RayHit> {
+        let path = Segment::from_arrow(ray.origin, ray.direction * max_distance);
+        let mut closest: Option<(f64, Vector)> = None;
+        for (segment, normal) in self.segments().into_iter().zip(self.normals()) {
+            if ray.direction * normal >= 0.0 {
+                continue;
+            }
+            if let Some((t, s)) = parametric_intersection(path, segment) {
+                let hit = (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&s);
+                let closer = match closest {
+                    Some((closest_t, _)) => t < closest_t,
+                    None => true,
+                };
+                if hit && closer {
+                    closest = Some((t, normal));
+                }
+            }
+        }
+        let (t, normal) = closest?;
+        return Some(RayHit {
+            distance: t * max_distance,
+            point: ray.point_at(t * max_distance),
+            normal,
+        });
+    }
+
+    // Whether any two edges cross, or an edge doubles back over the previous one
+    pub fn is_self_intersecting(&self) -> bool {
+        return self.has_crossing_edges() || self.doubles_back();
+    }
+
+    fn has_crossing_edges(&self) -> bool {
+        let segments = self.segments();
+        let len = segments.len();
+        for i in 0..len {
+            for j in i + 2..len {
+                if i == 0 && j == len - 1 {
+                    continue;
+                }
+                if intersection(segments[i], segments[j]).is_some() {
+                    return true;
+                }
+            }
+        }
+        return false;
+    }
+
+    fn doubles_back(&self) -> bool {
+        let segments = self.segments();
+        let len = segments.len();
+        return (0..len).any(|i| {
+            let (d1, d2) = (segments[i].direction(), segments[(i + 1) % len].direction());
+            (d1 ^ d2) == 0.0 && d1 * d2 < 0.0
+        });
+    }
+
+    // Positive for counter-clockwise vertices, negative for clockwise ones
+    fn signed_area(&self) -> f64 {
+        return signed_area(&self.vertices);
+    }
+
     // Mutators
     pub fn move_xy(&mut self, offset: Vector) {
         self.vertices.iter_mut().for_each(|v| *v += offset);
@@ -124,6 +450,14 @@ impl Polygon {
     pub fn move_y(&mut self, offset_y: f64) {
         self.move_xy(Vector::new(0.0, offset_y));
     }
+    pub fn rotate(&mut self, angle: f64) {
+        self.rotate_about(self.centroid(), angle);
+    }
+    pub fn rotate_about(&mut self, center: Point, angle: f64) {
+        self.vertices
+            .iter_mut()
+            .for_each(|v| *v = center + (*v - center).rotated_by(angle));
+    }
 }
 
 impl Similar for &Polygon {
@@ -132,11 +466,9 @@ impl Similar for &Polygon {
         A-B-C == B-C-A
         A-C-B != A-B-C
     */
-    /* TODO: We might want to have a more restricted invariant. Some ideas:
-        - represent convex polygons only (I think we are already assuming this somewhere)
-        - always have self.vertices sorted clockwise (take center/centroid as reference)
-        - have one constructor that sanitizes the input (i.e. computes the convex hull
-          and sorts the vertices) and another one with the adequate precondition for performance.
+    /* NOTE: polygons are assumed to be convex, but only `try_new` and
+        `convex_hull` enforce it (and sort the vertices counter-clockwise).
+        `new` keeps the vertices as given, for performance.
     */
     fn is_similar(self, other: &Polygon, eps: f64) -> bool {
         if self.vertices.len() != other.vertices.len() {
@@ -159,6 +491,30 @@ impl Similar for &Polygon {
     }
 }
 
+// Positive for counter-clockwise vertices, negative for clockwise ones
+pub(crate) fn signed_area(vertices: &[Point]) -> f64 {
+    let len = vertices.len();
+    let mut signed_area = 0.0;
+    for i in 0..len {
+        signed_area += vertices[i] ^ vertices[(i + 1) % len];
+    }
+    return signed_area / 2.0;
+}
+
+// Outward unit normal of the edge starting at each vertex, whatever the
+// winding. The two edges of a two vertex outline face opposite sides.
+pub(crate) fn outward_normals(vertices: &[Point]) -> Vec<Vector> {
+    let len = vertices.len();
+    let outwards = if signed_area(vertices) >= 0.0 {
+        1.0
+    } else {
+        -1.0
+    };
+    return (0..len)
+        .map(|i| (vertices[(i + 1) % len] - vertices[i]).perp().unit() * outwards)
+        .collect();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,6 +539,171 @@ mod tests {
         Polygon::new(&vertices);
     }
 
+    #[test]
+    fn try_new_sorts_counter_clockwise() {
+        let vertices = vec![
+            Point::new(0.0, 0.0),
+            Point::new(0.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 0.0),
+        ];
+        let p = Polygon::try_new(&vertices).unwrap();
+        let expected = vec![
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 1.0),
+            Point::new(0.0, 0.0),
+        ];
+        assert_eq!(p.vertices, expected);
+    }
+
+    #[test]
+    fn try_new_errors() {
+        let a = Point::new(0.0, 0.0);
+        let b = Point::new(2.0, 0.0);
+        let c = Point::new(2.0, 2.0);
+        let d = Point::new(0.0, 2.0);
+        let notch = Point::new(1.0, 1.0);
+
+        assert_eq!(
+            Polygon::try_new(&[a, b]),
+            Err(PolygonError::TooFewVertices(2))
+        );
+        assert_eq!(
+            Polygon::try_new(&[a, b, c, b]),
+            Err(PolygonError::RepeatedVertex(3))
+        );
+        assert_eq!(
+            Polygon::try_new(&[a, Point::new(1.0, 1.0), c]),
+            Err(PolygonError::ZeroArea)
+        );
+        assert_eq!(
+            Polygon::try_new(&[a, b, d, c]),
+            Err(PolygonError::SelfIntersecting)
+        );
+        assert_eq!(
+            Polygon::try_new(&[a, b, c, notch, d]),
+            Err(PolygonError::NotConvex)
+        );
+    }
+
+    #[test]
+    fn try_new_error_message() {
+        let error = Polygon::try_new(&[Point::new(-1.0, 0.0), Point::new(1.0, 2.0)]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "A polygon must be initialized with 3 or more vertices (2 used)"
+        );
+    }
+
+    #[test]
+    fn convex_hull() {
+        let points = vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 2.0),
+            Point::new(0.0, 2.0),
+            Point::new(1.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(0.5, 1.5),
+            Point::new(2.0, 0.0),
+        ];
+        let hull = Polygon::convex_hull(&points).unwrap();
+        let expected = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 2.0),
+            Point::new(0.0, 2.0),
+        ]);
+        assert_similar!(&hull, &expected);
+    }
+
+    #[test]
+    fn convex_hull_collinear() {
+        let points = vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 2.0),
+        ];
+        assert_eq!(Polygon::convex_hull(&points), Err(PolygonError::ZeroArea));
+    }
+
+    #[test]
+    fn triangulate() {
+        let p = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 2.0),
+            Point::new(0.0, 2.0),
+        ]);
+        let expected = vec![
+            [
+                Point::new(0.0, 2.0),
+                Point::new(0.0, 0.0),
+                Point::new(2.0, 0.0),
+            ],
+            [
+                Point::new(2.0, 0.0),
+                Point::new(2.0, 2.0),
+                Point::new(0.0, 2.0),
+            ],
+        ];
+        assert_similar!(&p.triangulate(), &expected);
+    }
+
+    #[test]
+    fn triangulate_clockwise_concave() {
+        let p = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(0.0, 2.0),
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 2.0),
+            Point::new(2.0, 0.0),
+        ]);
+        let triangles = p.triangulate();
+        assert_eq!(triangles.len(), 2);
+        for [a, b, c] in triangles.iter() {
+            assert!(((*b - *a) ^ (*c - *a)) > 0.0);
+        }
+        let area: f64 = triangles
+            .iter()
+            .map(|[a, b, c]| ((*b - *a) ^ (*c - *a)) / 2.0)
+            .sum();
+        assert_similar!(area, p.area());
+    }
+
+    #[test]
+    fn triangulate_collinear() {
+        let p = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(3.0, 0.0),
+            Point::new(3.0, 1.0),
+            Point::new(1.5, 1.0),
+            Point::new(0.0, 1.0),
+        ]);
+        let triangles = p.triangulate();
+        for [a, b, c] in triangles.iter() {
+            assert!(((*b - *a) ^ (*c - *a)) > 0.0);
+        }
+        let area: f64 = triangles
+            .iter()
+            .map(|[a, b, c]| ((*b - *a) ^ (*c - *a)) / 2.0)
+            .sum();
+        assert_similar!(area, 3.0);
+    }
+
+    #[test]
+    fn triangulate_degenerate() {
+        let p = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(2.0, 0.0),
+        ]);
+        assert!(p.triangulate().is_empty());
+    }
+
     #[test]
     fn segments() {
         let vertices = vec![
@@ -199,6 +720,37 @@ mod tests {
         assert_eq!(p.segments(), expected);
     }
 
+    #[test]
+    fn normals() {
+        let ccw = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 1.0),
+            Point::new(0.0, 1.0),
+        ]);
+        let expected = vec![
+            Vector::new(0.0, -1.0),
+            Vector::new(1.0, 0.0),
+            Vector::new(0.0, 1.0),
+            Vector::new(-1.0, 0.0),
+        ];
+        assert_similar!(&ccw.normals(), &expected);
+
+        let cw = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(0.0, 1.0),
+            Point::new(2.0, 1.0),
+            Point::new(2.0, 0.0),
+        ]);
+        let expected = vec![
+            Vector::new(-1.0, 0.0),
+            Vector::new(0.0, 1.0),
+            Vector::new(1.0, 0.0),
+            Vector::new(0.0, -1.0),
+        ];
+        assert_similar!(&cw.normals(), &expected);
+    }
+
     #[test]
     fn size() {
         let p = Polygon::new(&vec![
@@ -250,6 +802,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn area() {
+        let ccw = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(0.0, 3.0),
+        ]);
+        assert_similar!(ccw.area(), 3.0);
+
+        let cw = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(0.0, 3.0),
+            Point::new(2.0, 0.0),
+        ]);
+        assert_similar!(cw.area(), 3.0);
+    }
+
+    #[test]
+    fn moment_of_inertia() {
+        let p = Polygon::new(&vec![
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 2.0),
+            Point::new(3.0, 2.0),
+            Point::new(3.0, 1.0),
+        ]);
+        // m * (w^2 + h^2) / 12, with m = 2 * 3
+        assert_similar!(p.moment_of_inertia(3.0), 6.0 * 5.0 / 12.0);
+    }
+
+    #[test]
+    fn mass_data() {
+        let p = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(4.0, 0.0),
+            Point::new(4.0, 2.0),
+            Point::new(0.0, 2.0),
+        ]);
+        let expected = MassData {
+            mass: 16.0,
+            center: Point::new(2.0, 1.0),
+            inertia: 16.0 * 20.0 / 12.0,
+        };
+        assert_similar!(p.mass_data(2.0), expected);
+    }
+
     #[test]
     fn is_similar() {
         let p1 = Polygon::new(&vec![
@@ -281,6 +878,40 @@ mod tests {
         assert_similar!(*p.vertices.last().unwrap(), Point::new(1.0, 0.0));
     }
 
+    #[test]
+    fn rotate() {
+        let mut p = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 1.0),
+            Point::new(0.0, 1.0),
+        ]);
+        p.rotate(std::f64::consts::PI / 2.0);
+        let expected = Polygon::new(&vec![
+            Point::new(1.5, -0.5),
+            Point::new(1.5, 1.5),
+            Point::new(0.5, 1.5),
+            Point::new(0.5, -0.5),
+        ]);
+        assert_similar!(&p, &expected);
+    }
+
+    #[test]
+    fn rotate_about() {
+        let mut p = Polygon::new(&vec![
+            Point::new(1.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 1.0),
+        ]);
+        p.rotate_about(Point::zero(), std::f64::consts::PI);
+        let expected = Polygon::new(&vec![
+            Point::new(-1.0, 0.0),
+            Point::new(-2.0, 0.0),
+            Point::new(-2.0, -1.0),
+        ]);
+        assert_similar!(&p, &expected);
+    }
+
     #[test]
     fn move_x() {
         let mut p = Polygon::new(&vec![
@@ -306,4 +937,176 @@ mod tests {
         assert_similar!(*p.vertices.first().unwrap(), Point::new(0.0, -1.0));
         assert_similar!(*p.vertices.last().unwrap(), Point::new(0.0, 0.0));
     }
+
+    #[test]
+    fn aabb() {
+        let p = Polygon::new(&vec![
+            Point::new(1.0, -1.0),
+            Point::new(3.0, 2.0),
+            Point::new(-2.0, 0.5),
+        ]);
+        let aabb = p.aabb();
+        assert_similar!(aabb.min, Point::new(-2.0, -1.0));
+        assert_similar!(aabb.max, Point::new(3.0, 2.0));
+        assert_similar!(p.width(), 5.0);
+        assert_similar!(p.height(), 3.0);
+    }
+
+    #[test]
+    fn raycast() {
+        let p = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 1.0),
+            Point::new(0.0, 1.0),
+        ]);
+        let ray = Ray::new(Point::new(-1.0, 0.5), Vector::new(2.0, 0.0));
+        let expected = RayHit {
+            distance: 1.0,
+            point: Point::new(0.0, 0.5),
+            normal: Vector::new(-1.0, 0.0),
+        };
+        assert_similar!(p.raycast(&ray, 5.0), Some(expected));
+        assert_similar!(p.raycast(&ray, 0.5), Option::<RayHit>::None);
+
+        // Through a corner, and from the inside
+        let diagonal = Ray::new(Point::new(3.0, 2.0), Vector::new(-1.0, -1.0));
+        let hit = p.raycast(&diagonal, 5.0).unwrap();
+        assert_similar!(hit.point, Point::new(2.0, 1.0));
+        let inside = Ray::new(Point::new(1.0, 0.5), Vector::new(1.0, 0.0));
+        assert_similar!(p.raycast(&inside, 5.0), Option::<RayHit>::None);
+
+        // Same result whatever the winding
+        let mut clockwise = p.clone();
+        clockwise.vertices.reverse();
+        assert_similar!(clockwise.raycast(&ray, 5.0), Some(expected));
+    }
+
+    #[test]
+    fn split_by_line() {
+        let p = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 2.0),
+            Point::new(0.0, 2.0),
+        ]);
+        // Upwards, so the left piece is at x < 1
+        let (left, right) = p
+            .split_by_line(Segment::from_points(
+                Point::new(1.0, -5.0),
+                Point::new(1.0, -4.0),
+            ))
+            .unwrap();
+        let expected_left = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 2.0),
+            Point::new(0.0, 2.0),
+        ]);
+        let expected_right = Polygon::new(&vec![
+            Point::new(1.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 2.0),
+            Point::new(1.0, 2.0),
+        ]);
+        assert_similar!(&left, &expected_left);
+        assert_similar!(&right, &expected_right);
+
+        // Through two corners, keeping the winding
+        let mut clockwise = p.clone();
+        clockwise.vertices.reverse();
+        let diagonal = Segment::from_points(Point::new(0.0, 0.0), Point::new(2.0, 2.0));
+        let (left, right) = clockwise.split_by_line(diagonal).unwrap();
+        assert_similar!(
+            &left,
+            &Polygon::new(&vec![
+                Point::new(0.0, 2.0),
+                Point::new(2.0, 2.0),
+                Point::new(0.0, 0.0),
+            ])
+        );
+        assert_similar!(right.area(), 2.0);
+        assert!(right.signed_area() < 0.0);
+
+        // Missing or grazing
+        let outside = Segment::from_points(Point::new(3.0, 0.0), Point::new(3.0, 1.0));
+        assert_eq!(p.split_by_line(outside), None);
+        let along_edge = Segment::from_points(Point::new(2.0, 0.0), Point::new(2.0, 1.0));
+        assert_eq!(p.split_by_line(along_edge), None);
+        let through_corner = Segment::from_points(Point::new(1.0, 3.0), Point::new(3.0, 1.0));
+        assert_eq!(p.split_by_line(through_corner), None);
+    }
+
+    #[test]
+    fn minkowski_sum() {
+        let square = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 1.0),
+        ]);
+        // Clockwise, centered on the origin
+        let diamond = Polygon::new(&vec![
+            Point::new(0.0, 1.0),
+            Point::new(1.0, 0.0),
+            Point::new(0.0, -1.0),
+            Point::new(-1.0, 0.0),
+        ]);
+        let sum = square.minkowski_sum(&diamond);
+        let expected = Polygon::new(&vec![
+            Point::new(0.0, -1.0),
+            Point::new(1.0, -1.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 1.0),
+            Point::new(1.0, 2.0),
+            Point::new(0.0, 2.0),
+            Point::new(-1.0, 1.0),
+            Point::new(-1.0, 0.0),
+        ]);
+        assert_similar!(&sum, &expected);
+        assert_similar!(sum.area(), 1.0 + 4.0 * 1.0 + 2.0);
+    }
+
+    #[test]
+    fn offset() {
+        let square = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 2.0),
+            Point::new(0.0, 2.0),
+        ]);
+        let grown = square.offset(0.5).unwrap();
+        let expected = Polygon::new(&vec![
+            Point::new(-0.5, -0.5),
+            Point::new(2.5, -0.5),
+            Point::new(2.5, 2.5),
+            Point::new(-0.5, 2.5),
+        ]);
+        assert_similar!(&grown, &expected);
+        let mut clockwise = square.clone();
+        clockwise.vertices.reverse();
+        assert_similar!(clockwise.offset(0.5).unwrap().area(), 9.0);
+
+        let shrunk = square.offset(-0.5).unwrap();
+        assert_similar!(shrunk.area(), 1.0);
+        assert_similar!(shrunk.centroid(), Point::new(1.0, 1.0));
+        assert_eq!(square.offset(-1.0), None);
+        assert_eq!(square.offset(-2.0), None);
+
+        // The short edge of the trapezoid vanishes when shrinking
+        let trapezoid = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(4.0, 0.0),
+            Point::new(2.1, 1.9),
+            Point::new(1.9, 1.9),
+        ]);
+        let shrunk = trapezoid.offset(-0.5).unwrap();
+        let shift = 0.5 * 2f64.sqrt();
+        let expected = Polygon::new(&vec![
+            Point::new(0.5 + shift, 0.5),
+            Point::new(3.5 - shift, 0.5),
+            Point::new(2.0, 2.0 - shift),
+        ]);
+        assert_similar!(&shrunk, &expected);
+    }
 }
@@ -0,0 +1,48 @@
+use crate::Point;
+use crate::Vector;
+
+use similar::Similar;
+use similar_derive::Similar;
+
+/// A half-line starting at `origin`. The direction is kept as a unit vector,
+/// so parameters along the ray are distances.
+#[derive(Copy, Clone, Debug, PartialEq, Similar)]
+pub struct Ray {
+    pub origin: Point,
+    pub direction: Vector,
+}
+
+/// Where a ray first enters a shape: the distance from the origin of the ray,
+/// the point, and the outward normal of the shape at that point.
+#[derive(Copy, Clone, Debug, PartialEq, Similar)]
+pub struct RayHit {
+    pub distance: f64,
+    pub point: Point,
+    pub normal: Vector,
+}
+
+impl Ray {
+    pub fn new(origin: Point, direction: Vector) -> Ray {
+        Ray {
+            origin,
+            direction: direction.unit(),
+        }
+    }
+
+    pub fn point_at(self, distance: f64) -> Point {
+        return self.origin + self.direction * distance;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar::assert_similar;
+
+    #[test]
+    fn point_at() {
+        let ray = Ray::new(Point::new(1.0, 1.0), Vector::new(0.0, 2.0));
+        assert_similar!(ray.direction, Vector::new(0.0, 1.0));
+        assert_similar!(ray.point_at(3.0), Point::new(1.0, 4.0));
+    }
+}
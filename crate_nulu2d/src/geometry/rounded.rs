@@ -0,0 +1,427 @@
+// Collision of rounded convex shapes: a convex core (a single vertex for
+// circles, two for capsules, three or more for polygons) inflated by a
+// radius. Two of them overlap when the distance between their cores is
+// smaller than the sum of their radii, which is the distance from the origin
+// to the Minkowski difference of the cores.
+use crate::geometry::contact::CONTACT_MARGIN;
+use crate::geometry::decomposition::monotone_chain;
+use crate::geometry::polygon::outward_normals;
+use crate::ContactManifold;
+use crate::ContactPoint;
+use crate::Feature;
+use crate::FeatureId;
+use crate::Penetration;
+use crate::Point;
+use crate::Segment;
+use crate::Vector;
+
+// How close to parallel (cosine of the angle) two faces must be to produce
+// two contact points instead of one
+const FLAT_CONTACT: f64 = 0.999;
+
+// Vertices of `b - a` for every pair of points, sorted counter-clockwise
+pub(crate) fn minkowski_difference(core_a: &[Point], core_b: &[Point]) -> Vec<Point> {
+    let differences: Vec<Point> = core_b
+        .iter()
+        .flat_map(|b| core_a.iter().map(move |a| *b - *a))
+        .collect();
+    return monotone_chain(&differences);
+}
+
+// Vertices of `a + b` for every pair of points, sorted counter-clockwise
+pub(crate) fn minkowski_sum(core_a: &[Point], core_b: &[Point]) -> Vec<Point> {
+    let sums: Vec<Point> = core_b
+        .iter()
+        .flat_map(|b| core_a.iter().map(move |a| *a + *b))
+        .collect();
+    return monotone_chain(&sums);
+}
+
+// Edges of a core along with their outward unit normals, whatever the winding
+// of its vertices. A two vertex core has two edges, one facing each side.
+pub(crate) fn edges(core: &[Point]) -> Vec<(Segment, Vector)> {
+    let len = core.len();
+    if len < 2 {
+        return Vec::new();
+    }
+    let normals = outward_normals(core);
+    return (0..len)
+        .map(|i| {
+            (
+                Segment::from_points(core[i], core[(i + 1) % len]),
+                normals[i],
+            )
+        })
+        .filter(|(s, _)| s.direction().norm() > f64::EPSILON)
+        .collect();
+}
+
+// Signed distance from a convex core to a point (negative inside), along with
+// the outward normal of the core towards the point
+pub(crate) fn signed_distance(core: &[Point], point: Point) -> (f64, Vector) {
+    let edges = edges(core);
+    if core.len() >= 3 {
+        let (separation, normal) = edges.iter().map(|(s, n)| ((point - s.a) * *n, *n)).fold(
+            (f64::NEG_INFINITY, Vector::zero()),
+            |max, e| {
+                if e.0 > max.0 {
+                    e
+                } else {
+                    max
+                }
+            },
+        );
+        if separation <= 0.0 {
+            return (separation, normal);
+        }
+    }
+
+    let closest = edges
+        .iter()
+        .map(|(s, _)| s.closest_point(point))
+        .fold(core[0], |best, p| {
+            if p.distance(point) < best.distance(point) {
+                p
+            } else {
+                best
+            }
+        });
+    let distance = closest.distance(point);
+    if distance > f64::EPSILON {
+        return (distance, (point - closest) / distance);
+    }
+    // On a thin core, any side will do
+    let normal = edges
+        .first()
+        .map(|(_, n)| *n)
+        .unwrap_or(Vector::new(0.0, 1.0));
+    return (0.0, normal);
+}
+
+// Penetration of two rounded shapes, with the normal pointing from a to b
+pub(crate) fn rounded_penetration(
+    core_a: &[Point],
+    radius_a: f64,
+    core_b: &[Point],
+    radius_b: f64,
+) -> Option<Penetration> {
+    let difference = minkowski_difference(core_a, core_b);
+    // Moving b moves the difference, which pushes the origin out through the
+    // opposite side
+    let (distance, normal) = signed_distance(&difference, Point::zero());
+    let depth = radius_a + radius_b - distance;
+    if depth < 0.0 {
+        return None;
+    }
+    return Some(Penetration {
+        depth,
+        normal: normal * -1.0,
+    });
+}
+
+// First time at which a point moving at `velocity` gets within `radius` of a
+// convex core, along with the outward normal at that point. Returns `None` if
+// it starts within the radius, or never gets there.
+pub(crate) fn sweep_point(
+    core: &[Point],
+    radius: f64,
+    point: Point,
+    velocity: Vector,
+) -> Option<(f64, Vector)> {
+    if signed_distance(core, point).0 <= radius {
+        return None;
+    }
+    let mut hit: Option<(f64, Vector)> = None;
+    let mut update = |time: f64, normal: Vector| {
+        if hit.is_none_or(|(t, _)| time < t) {
+            hit = Some((time, normal));
+        }
+    };
+
+    // Edges, pushed out by the radius
+    for (segment, normal) in edges(core) {
+        let approach = velocity * normal;
+        if approach < -1e-12 {
+            let time = (radius - (point - segment.a) * normal) / approach;
+            let along = (point + velocity * time - segment.a) * segment.direction()
+                / (segment.direction() * segment.direction());
+            if time >= 0.0 && (0.0..=1.0).contains(&along) {
+                update(time, normal);
+            }
+        }
+    }
+
+    // Rounded corners
+    for vertex in core {
+        let relative = point - *vertex;
+        if let Some(time) = sweep_point_circle(relative, velocity, radius) {
+            update(time, (relative + velocity * time).unit());
+        }
+    }
+    return hit;
+}
+
+// First time at which a point at `position` moving at `velocity` gets to
+// `radius` from the origin, if it's outside and getting closer
+fn sweep_point_circle(position: Point, velocity: Vector, radius: f64) -> Option<f64> {
+    let a = velocity * velocity;
+    if a <= f64::EPSILON * f64::EPSILON {
+        return None;
+    }
+    let b = position * velocity;
+    let c = position * position - radius * radius;
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let time = (-b - discriminant.sqrt()) / a;
+    if time < 0.0 {
+        return None;
+    }
+    return Some(time);
+}
+
+// Contact manifold of two rounded shapes. Points lie on the surface of b.
+// Two nearly parallel faces give two points, anything else a single one.
+pub(crate) fn rounded_contact_manifold(
+    core_a: &[Point],
+    radius_a: f64,
+    core_b: &[Point],
+    radius_b: f64,
+) -> Option<ContactManifold> {
+    let penetration = rounded_penetration(core_a, radius_a, core_b, radius_b + CONTACT_MARGIN)?;
+    return Some(contact_along(
+        core_a,
+        radius_a,
+        core_b,
+        radius_b,
+        Penetration {
+            depth: penetration.depth - CONTACT_MARGIN,
+            normal: penetration.normal,
+        },
+    ));
+}
+
+// Contact manifold of two rounded shapes touching along a known normal
+pub(crate) fn contact_along(
+    core_a: &[Point],
+    radius_a: f64,
+    core_b: &[Point],
+    radius_b: f64,
+    penetration: Penetration,
+) -> ContactManifold {
+    let Penetration { depth, normal } = penetration;
+    let face_a = support_face(core_a, normal);
+    let face_b = support_face(core_b, normal * -1.0);
+    if let (Feature::Edge(ea), Feature::Edge(eb)) = (face_a.0, face_b.0) {
+        let points = clip_faces(
+            (ea, face_a.1, radius_a),
+            (eb, face_b.1, radius_b),
+            core_a.len(),
+            core_b.len(),
+            normal,
+        );
+        if !points.is_empty() {
+            return ContactManifold { normal, points };
+        }
+    }
+
+    // A single point, from the vertex that's touching
+    let (point, id) = match (face_a.0, face_b.0) {
+        (_, Feature::Vertex(j)) => (
+            core_b[j] - normal * radius_b,
+            FeatureId {
+                a: face_a.0,
+                b: Feature::Vertex(j),
+            },
+        ),
+        (Feature::Vertex(i), _) => (
+            core_a[i] + normal * (radius_a - depth),
+            FeatureId {
+                a: Feature::Vertex(i),
+                b: face_b.0,
+            },
+        ),
+        // Facing edges that are too far apart: use the deepest vertex
+        (_, _) => {
+            let j = support_vertex(core_b, normal * -1.0);
+            (
+                core_b[j] - normal * radius_b,
+                FeatureId {
+                    a: face_a.0,
+                    b: Feature::Vertex(j),
+                },
+            )
+        }
+    };
+    return ContactManifold {
+        normal,
+        points: vec![ContactPoint { point, depth, id }],
+    };
+}
+
+// The edge of the core facing `direction` if it's nearly perpendicular to it,
+// otherwise the farthest vertex along it
+fn support_face(core: &[Point], direction: Vector) -> (Feature, Segment) {
+    let len = core.len();
+    if len >= 2 {
+        let normals = outward_normals(core);
+        for i in 0..len {
+            let segment = Segment::from_points(core[i], core[(i + 1) % len]);
+            if segment.direction().norm() > f64::EPSILON && normals[i] * direction >= FLAT_CONTACT {
+                return (Feature::Edge(i), segment);
+            }
+        }
+    }
+    let i = support_vertex(core, direction);
+    return (Feature::Vertex(i), Segment::from_points(core[i], core[i]));
+}
+
+fn support_vertex(core: &[Point], direction: Vector) -> usize {
+    return (0..core.len())
+        .fold((0, f64::NEG_INFINITY), |(best, max), i| {
+            if core[i] * direction > max {
+                (i, core[i] * direction)
+            } else {
+                (best, max)
+            }
+        })
+        .0;
+}
+
+// Contact points of two facing edges, at both ends of their common span
+// along the tangent. Points too far apart to be touching are dropped.
+fn clip_faces(
+    (ea, face_a, radius_a): (usize, Segment, f64),
+    (eb, face_b, radius_b): (usize, Segment, f64),
+    len_a: usize,
+    len_b: usize,
+    normal: Vector,
+) -> Vec<ContactPoint> {
+    let tangent = normal.perp();
+    let along = |face: Segment, s: f64| {
+        let t = (s - face.a * tangent) / (face.direction() * tangent);
+        face.a + face.direction() * t
+    };
+    // Ends of each face along the tangent, with the vertex they come from
+    let ends = |face: Segment, edge: usize, len: usize| {
+        let (sa, sb) = (face.a * tangent, face.b * tangent);
+        let (ia, ib) = (Feature::Vertex(edge), Feature::Vertex((edge + 1) % len));
+        if sa <= sb {
+            ((sa, ia), (sb, ib))
+        } else {
+            ((sb, ib), (sa, ia))
+        }
+    };
+    let (low_a, high_a) = ends(face_a, ea, len_a);
+    let (low_b, high_b) = ends(face_b, eb, len_b);
+
+    let low = if low_b.0 >= low_a.0 {
+        (low_b.0, Feature::Edge(ea), low_b.1)
+    } else {
+        (low_a.0, low_a.1, Feature::Edge(eb))
+    };
+    let high = if high_b.0 <= high_a.0 {
+        (high_b.0, Feature::Edge(ea), high_b.1)
+    } else {
+        (high_a.0, high_a.1, Feature::Edge(eb))
+    };
+    // Faces that only meet at one spot (or don't overlap at all along the
+    // tangent) touch at the nearest end of b
+    let ends = if high.0 - low.0 > f64::EPSILON {
+        vec![low, high]
+    } else if let Feature::Vertex(_) = low.2 {
+        vec![low]
+    } else {
+        vec![high]
+    };
+
+    return ends
+        .into_iter()
+        .map(|(s, a, b)| {
+            let (pa, pb) = (along(face_a, s), along(face_b, s));
+            ContactPoint {
+                point: pb - normal * radius_b,
+                depth: radius_a + radius_b - (pb - pa) * normal,
+                id: FeatureId { a, b },
+            }
+        })
+        .filter(|p| p.depth >= -CONTACT_MARGIN)
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::square;
+    use similar::assert_similar;
+    use similar::Similar;
+
+    #[test]
+    fn signed_distance_inside_and_outside() {
+        let (distance, normal) =
+            signed_distance(&square(0.0, 0.0, 1.0).vertices, Point::new(0.9, 0.5));
+        assert_similar!(distance, -0.1);
+        assert_similar!(normal, Vector::new(1.0, 0.0));
+        let (distance, normal) =
+            signed_distance(&square(0.0, 0.0, 1.0).vertices, Point::new(1.3, 1.4));
+        assert_similar!(distance, 0.5);
+        assert_similar!(normal, Vector::new(0.6, 0.8));
+    }
+
+    #[test]
+    fn penetration_of_segments() {
+        let a = [Point::new(0.0, 0.0), Point::new(4.0, 0.0)];
+        let b = [Point::new(1.0, 0.5), Point::new(3.0, 0.5)];
+        let expected = Penetration {
+            depth: 0.5,
+            normal: Vector::new(0.0, 1.0),
+        };
+        assert_similar!(rounded_penetration(&a, 0.5, &b, 0.5), Some(expected));
+        assert_similar!(
+            rounded_penetration(&a, 0.2, &b, 0.2),
+            Option::<Penetration>::None
+        );
+    }
+
+    #[test]
+    fn sweep_point_into_rounded_square() {
+        let (time, normal) = sweep_point(
+            &square(0.0, 0.0, 1.0).vertices,
+            0.5,
+            Point::new(0.5, 3.0),
+            Vector::new(0.0, -1.0),
+        )
+        .unwrap();
+        assert_similar!(time, 1.5);
+        assert_similar!(normal, Vector::new(0.0, 1.0));
+        assert_eq!(
+            sweep_point(
+                &square(0.0, 0.0, 1.0).vertices,
+                0.5,
+                Point::new(0.5, 3.0),
+                Vector::new(0.0, 1.0)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn flat_manifold_has_two_points() {
+        let floor = [
+            Point::new(-5.0, -1.0),
+            Point::new(5.0, -1.0),
+            Point::new(5.0, 0.0),
+            Point::new(-5.0, 0.0),
+        ];
+        let capsule = [Point::new(-1.0, 0.4), Point::new(1.0, 0.4)];
+        let manifold = rounded_contact_manifold(&floor, 0.0, &capsule, 0.5).unwrap();
+        assert_similar!(manifold.normal, Vector::new(0.0, 1.0));
+        assert_eq!(manifold.points.len(), 2);
+        for point in manifold.points.iter() {
+            assert_similar!(point.depth, 0.1);
+            assert_similar!(point.point.y, -0.1);
+            assert_eq!(point.id.a, Feature::Edge(2));
+        }
+    }
+}
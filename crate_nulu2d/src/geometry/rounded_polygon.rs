@@ -0,0 +1,257 @@
+use crate::geometry::rounded::edges;
+use crate::geometry::rounded::signed_distance;
+use crate::geometry::rounded::sweep_point;
+use crate::Aabb;
+use crate::MassData;
+use crate::Point;
+use crate::Polygon;
+use crate::Ray;
+use crate::RayHit;
+use crate::Vector;
+
+use std::f64::consts;
+
+/// A convex polygon inflated by a radius, like a capsule is a segment
+/// inflated by one. A small radius (a "skin") keeps contacts stable.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoundedPolygon {
+    pub polygon: Polygon,
+    pub radius: f64,
+}
+
+impl RoundedPolygon {
+    pub fn new(polygon: Polygon, radius: f64) -> RoundedPolygon {
+        RoundedPolygon { polygon, radius }
+    }
+
+    // Accessors
+    pub fn aabb(&self) -> Aabb {
+        return self.polygon.aabb().expanded(self.radius);
+    }
+
+    pub fn width(&self) -> f64 {
+        return self.aabb().width();
+    }
+
+    pub fn height(&self) -> f64 {
+        return self.aabb().height();
+    }
+
+    pub fn top(&self) -> f64 {
+        return self.aabb().max.y;
+    }
+
+    pub fn bottom(&self) -> f64 {
+        return self.aabb().min.y;
+    }
+
+    pub fn left(&self) -> f64 {
+        return self.aabb().min.x;
+    }
+
+    pub fn right(&self) -> f64 {
+        return self.aabb().max.x;
+    }
+
+    pub fn centroid(&self) -> Point {
+        return self.mass_data(1.0).center;
+    }
+
+    // The polygon, a rectangle along each edge, and a circular sector at each
+    // vertex, which add up to a full circle
+    pub fn area(&self) -> f64 {
+        let perimeter: f64 = self
+            .polygon
+            .segments()
+            .iter()
+            .map(|s| s.direction().norm())
+            .sum();
+        return self.polygon.area() + perimeter * self.radius + consts::PI * self.radius.powi(2);
+    }
+
+    // Around the centroid
+    pub fn moment_of_inertia(&self, density: f64) -> f64 {
+        return self.mass_data(density).inertia;
+    }
+
+    pub fn mass_data(&self, density: f64) -> MassData {
+        let r = self.radius;
+        // Area, centroid and inertia (per unit of density, around the
+        // centroid) of each piece, starting with the polygon
+        let mut pieces = vec![(
+            self.polygon.area(),
+            self.polygon.centroid(),
+            self.polygon.moment_of_inertia(1.0),
+        )];
+        let edges = edges(&self.polygon.vertices);
+        for (i, (segment, normal)) in edges.iter().enumerate() {
+            let length = segment.direction().norm();
+            let area = length * r;
+            let center = segment.a + segment.direction() / 2.0 + *normal * (r / 2.0);
+            pieces.push((area, center, area * (length * length + r * r) / 12.0));
+
+            // The sector between this edge and the next one
+            let next = edges[(i + 1) % edges.len()].1;
+            let angle = (*normal ^ next).abs().atan2(*normal * next);
+            if angle > f64::EPSILON {
+                let area = angle * r * r / 2.0;
+                let offset = 4.0 * r * (angle / 2.0).sin() / (3.0 * angle);
+                let center = segment.b + (*normal + next).unit() * offset;
+                let inertia = angle * r.powi(4) / 4.0 - area * offset * offset;
+                pieces.push((area, center, inertia));
+            }
+        }
+
+        let area: f64 = pieces.iter().map(|(a, _, _)| *a).sum();
+        let center = pieces
+            .iter()
+            .fold(Point::zero(), |sum, (a, c, _)| sum + *c * *a)
+            / area;
+        let inertia: f64 = pieces
+            .iter()
+            .map(|(a, c, i)| *i + *a * ((*c - center) * (*c - center)))
+            .sum();
+        return MassData {
+            mass: density * area,
+            center,
+            inertia: density * inertia,
+        };
+    }
+
+    pub fn contains_point(&self, point: Point) -> bool {
+        return signed_distance(&self.polygon.vertices, point).0 <= self.radius;
+    }
+
+    // Returns `None` if the ray starts inside the rounded polygon
+    pub fn raycast(&self, ray: &Ray, max_distance: f64) -> Option<RayHit> {
+        let (distance, normal) = sweep_point(
+            &self.polygon.vertices,
+            self.radius,
+            ray.origin,
+            ray.direction,
+        )?;
+        if distance > max_distance {
+            return None;
+        }
+        return Some(RayHit {
+            distance,
+            point: ray.point_at(distance),
+            normal,
+        });
+    }
+
+    // Mutators
+    pub fn move_xy(&mut self, offset: Vector) {
+        self.polygon.move_xy(offset);
+    }
+    pub fn move_x(&mut self, offset_x: f64) {
+        self.move_xy(Vector::new(offset_x, 0.0));
+    }
+    pub fn move_y(&mut self, offset_y: f64) {
+        self.move_xy(Vector::new(0.0, offset_y));
+    }
+    pub fn rotate(&mut self, angle: f64) {
+        self.rotate_about(self.centroid(), angle);
+    }
+    pub fn rotate_about(&mut self, center: Point, angle: f64) {
+        self.polygon.rotate_about(center, angle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::square;
+    use crate::Capsule;
+    use crate::Segment;
+    use similar::assert_similar;
+    use similar::Similar;
+
+    fn rounded_square() -> RoundedPolygon {
+        return RoundedPolygon::new(square(0.0, 0.0, 2.0), 0.5);
+    }
+
+    #[test]
+    fn bounds() {
+        let rounded = rounded_square();
+        assert_similar!(rounded.width(), 3.0);
+        assert_similar!(rounded.left(), -0.5);
+        assert_similar!(rounded.top(), 2.5);
+        assert_similar!(rounded.centroid(), Point::new(1.0, 1.0));
+        assert!(rounded.contains_point(Point::new(2.4, 1.0)));
+        assert!(!rounded.contains_point(Point::new(2.4, 2.4)));
+    }
+
+    #[test]
+    fn mass_matches_polygon() {
+        // The square swept by a finely divided circle
+        let circle = Polygon::new(
+            &(0..1000)
+                .map(|i| Point::from_polar(i as f64 * consts::PI / 500.0, 0.5))
+                .collect(),
+        );
+        let rounded = rounded_square();
+        let polygon = rounded.polygon.minkowski_sum(&circle);
+        assert_similar!(rounded.area(), 4.0 + 4.0 + consts::PI * 0.25);
+        assert_similar!(rounded.area(), polygon.area(), 1e-4);
+        assert_similar!(
+            rounded.moment_of_inertia(2.0),
+            polygon.moment_of_inertia(2.0),
+            1e-3
+        );
+
+        // Off center, with a clockwise triangle
+        let triangle = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 3.0),
+            Point::new(2.0, 0.0),
+        ]);
+        let rounded = RoundedPolygon::new(triangle.clone(), 0.5);
+        let polygon = triangle.minkowski_sum(&circle);
+        assert_similar!(rounded.centroid(), polygon.centroid(), 1e-4);
+        assert_similar!(
+            rounded.moment_of_inertia(1.0),
+            polygon.moment_of_inertia(1.0),
+            1e-3
+        );
+    }
+
+    #[test]
+    fn thin_rectangle_is_a_capsule() {
+        let thin = Polygon::new(&vec![
+            Point::new(-1.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1e-9),
+            Point::new(-1.0, 1e-9),
+        ]);
+        let rounded = RoundedPolygon::new(thin, 0.5);
+        let capsule = Capsule::new(
+            Segment::from_points(Point::new(-1.0, 0.0), Point::new(1.0, 0.0)),
+            0.5,
+        );
+        assert_similar!(rounded.area(), capsule.area(), 1e-6);
+        assert_similar!(
+            rounded.moment_of_inertia(1.0),
+            capsule.moment_of_inertia(1.0),
+            1e-6
+        );
+    }
+
+    #[test]
+    fn raycast_side_and_corner() {
+        let rounded = rounded_square();
+        let down = Ray::new(Point::new(1.0, 5.0), Vector::new(0.0, -1.0));
+        let hit = rounded.raycast(&down, 10.0).unwrap();
+        assert_similar!(hit.point, Point::new(1.0, 2.5));
+        assert_similar!(hit.normal, Vector::new(0.0, 1.0));
+
+        let diagonal = Ray::new(Point::new(5.0, 5.0), Vector::new(-1.0, -1.0));
+        let hit = rounded.raycast(&diagonal, 10.0).unwrap();
+        let corner = Point::new(2.0, 2.0) + Vector::new(1.0, 1.0).unit() * 0.5;
+        assert_similar!(hit.point, corner);
+        assert_similar!(hit.normal, Vector::new(1.0, 1.0).unit());
+
+        let inside = Ray::new(Point::new(1.0, 1.0), Vector::new(1.0, 0.0));
+        assert_eq!(rounded.raycast(&inside, 10.0), None);
+    }
+}
@@ -32,6 +32,17 @@ impl Segment {
     pub fn center(self) -> Point {
         self.a
     }
+
+    // Point of the segment nearest to `point`
+    pub fn closest_point(self, point: Point) -> Point {
+        let direction = self.direction();
+        let length_squared = direction * direction;
+        if length_squared <= f64::EPSILON {
+            return self.a;
+        }
+        let t = ((point - self.a) * direction / length_squared).clamp(0.0, 1.0);
+        return self.a + direction * t;
+    }
 }
 
 #[cfg(test)]
@@ -65,4 +76,12 @@ mod tests {
         let s = Segment::from_points(Point::new(0.0, 1.0), Point::new(1.0, 0.0));
         assert_similar!(s.center(), Point::new(0.0, 1.0));
     }
+
+    #[test]
+    fn closest_point() {
+        let s = Segment::from_points(Point::new(0.0, 0.0), Point::new(2.0, 0.0));
+        assert_similar!(s.closest_point(Point::new(1.0, 3.0)), Point::new(1.0, 0.0));
+        assert_similar!(s.closest_point(Point::new(-1.0, 1.0)), Point::new(0.0, 0.0));
+        assert_similar!(s.closest_point(Point::new(5.0, -1.0)), Point::new(2.0, 0.0));
+    }
 }
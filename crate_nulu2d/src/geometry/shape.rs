@@ -0,0 +1,456 @@
+use crate::geometry::collision::circles_penetration;
+use crate::geometry::collision::penetration;
+use crate::geometry::collision::polygon_circle_penetration;
+use crate::geometry::contact::circles_contact_manifold;
+use crate::geometry::contact::contact_manifold;
+use crate::geometry::contact::polygon_circle_contact_manifold;
+use crate::geometry::edge::edge_contact_manifold;
+use crate::geometry::edge::edge_penetration;
+use crate::geometry::rounded::rounded_contact_manifold;
+use crate::geometry::rounded::rounded_penetration;
+use crate::Aabb;
+use crate::Capsule;
+use crate::Circle;
+use crate::ContactManifold;
+use crate::Edge;
+use crate::MassData;
+use crate::Penetration;
+use crate::Point;
+use crate::Polygon;
+use crate::Ray;
+use crate::RayHit;
+use crate::RoundedPolygon;
+use crate::Transform;
+use crate::Vector;
+
+/// Any of the shapes a body can have. Polygons are expected to be convex.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Polygon(Polygon),
+    Circle(Circle),
+    Capsule(Capsule),
+    RoundedPolygon(RoundedPolygon),
+    Edge(Edge),
+}
+
+impl From<Polygon> for Shape {
+    fn from(polygon: Polygon) -> Shape {
+        Shape::Polygon(polygon)
+    }
+}
+
+impl From<Circle> for Shape {
+    fn from(circle: Circle) -> Shape {
+        Shape::Circle(circle)
+    }
+}
+
+impl From<Capsule> for Shape {
+    fn from(capsule: Capsule) -> Shape {
+        Shape::Capsule(capsule)
+    }
+}
+
+impl From<RoundedPolygon> for Shape {
+    fn from(rounded: RoundedPolygon) -> Shape {
+        Shape::RoundedPolygon(rounded)
+    }
+}
+
+impl From<Edge> for Shape {
+    fn from(edge: Edge) -> Shape {
+        Shape::Edge(edge)
+    }
+}
+
+impl Shape {
+    // Accessors
+    pub fn aabb(&self) -> Aabb {
+        match self {
+            Shape::Polygon(polygon) => polygon.aabb(),
+            Shape::Circle(circle) => circle.aabb(),
+            Shape::Capsule(capsule) => capsule.aabb(),
+            Shape::RoundedPolygon(rounded) => rounded.aabb(),
+            Shape::Edge(edge) => edge.aabb(),
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        match self {
+            Shape::Polygon(polygon) => polygon.width(),
+            Shape::Circle(circle) => circle.width(),
+            Shape::Capsule(capsule) => capsule.width(),
+            Shape::RoundedPolygon(rounded) => rounded.width(),
+            Shape::Edge(edge) => edge.width(),
+        }
+    }
+
+    pub fn height(&self) -> f64 {
+        match self {
+            Shape::Polygon(polygon) => polygon.height(),
+            Shape::Circle(circle) => circle.height(),
+            Shape::Capsule(capsule) => capsule.height(),
+            Shape::RoundedPolygon(rounded) => rounded.height(),
+            Shape::Edge(edge) => edge.height(),
+        }
+    }
+
+    pub fn top(&self) -> f64 {
+        match self {
+            Shape::Polygon(polygon) => polygon.top(),
+            Shape::Circle(circle) => circle.top(),
+            Shape::Capsule(capsule) => capsule.top(),
+            Shape::RoundedPolygon(rounded) => rounded.top(),
+            Shape::Edge(edge) => edge.top(),
+        }
+    }
+
+    pub fn bottom(&self) -> f64 {
+        match self {
+            Shape::Polygon(polygon) => polygon.bottom(),
+            Shape::Circle(circle) => circle.bottom(),
+            Shape::Capsule(capsule) => capsule.bottom(),
+            Shape::RoundedPolygon(rounded) => rounded.bottom(),
+            Shape::Edge(edge) => edge.bottom(),
+        }
+    }
+
+    pub fn left(&self) -> f64 {
+        match self {
+            Shape::Polygon(polygon) => polygon.left(),
+            Shape::Circle(circle) => circle.left(),
+            Shape::Capsule(capsule) => capsule.left(),
+            Shape::RoundedPolygon(rounded) => rounded.left(),
+            Shape::Edge(edge) => edge.left(),
+        }
+    }
+
+    pub fn right(&self) -> f64 {
+        match self {
+            Shape::Polygon(polygon) => polygon.right(),
+            Shape::Circle(circle) => circle.right(),
+            Shape::Capsule(capsule) => capsule.right(),
+            Shape::RoundedPolygon(rounded) => rounded.right(),
+            Shape::Edge(edge) => edge.right(),
+        }
+    }
+
+    pub fn centroid(&self) -> Point {
+        match self {
+            Shape::Polygon(polygon) => polygon.centroid(),
+            Shape::Circle(circle) => circle.center,
+            Shape::Capsule(capsule) => capsule.center(),
+            Shape::RoundedPolygon(rounded) => rounded.centroid(),
+            Shape::Edge(edge) => edge.center(),
+        }
+    }
+
+    pub fn area(&self) -> f64 {
+        match self {
+            Shape::Polygon(polygon) => polygon.area(),
+            Shape::Circle(circle) => circle.area(),
+            Shape::Capsule(capsule) => capsule.area(),
+            Shape::RoundedPolygon(rounded) => rounded.area(),
+            Shape::Edge(edge) => edge.area(),
+        }
+    }
+
+    // Around the centroid
+    pub fn moment_of_inertia(&self, density: f64) -> f64 {
+        match self {
+            Shape::Polygon(polygon) => polygon.moment_of_inertia(density),
+            Shape::Circle(circle) => circle.moment_of_inertia(density),
+            Shape::Capsule(capsule) => capsule.moment_of_inertia(density),
+            Shape::RoundedPolygon(rounded) => rounded.moment_of_inertia(density),
+            Shape::Edge(edge) => edge.moment_of_inertia(density),
+        }
+    }
+
+    pub fn mass_data(&self, density: f64) -> MassData {
+        match self {
+            Shape::Polygon(polygon) => polygon.mass_data(density),
+            Shape::Circle(circle) => circle.mass_data(density),
+            Shape::Capsule(capsule) => capsule.mass_data(density),
+            Shape::RoundedPolygon(rounded) => rounded.mass_data(density),
+            Shape::Edge(edge) => edge.mass_data(density),
+        }
+    }
+
+    pub fn raycast(&self, ray: &Ray, max_distance: f64) -> Option<RayHit> {
+        match self {
+            Shape::Polygon(polygon) => polygon.raycast(ray, max_distance),
+            Shape::Circle(circle) => circle.raycast(ray, max_distance),
+            Shape::Capsule(capsule) => capsule.raycast(ray, max_distance),
+            Shape::RoundedPolygon(rounded) => rounded.raycast(ray, max_distance),
+            Shape::Edge(edge) => edge.raycast(ray, max_distance),
+        }
+    }
+
+    // Collision
+    /// Returns whether two shapes overlap. Touching borders count as
+    /// overlapping.
+    pub fn overlaps(&self, other: &Shape) -> bool {
+        return self.penetration(other).is_some();
+    }
+
+    /// Returns the penetration of two shapes, or `None` if they don't overlap.
+    /// The normal points from `self` towards `other`.
+    pub fn penetration(&self, other: &Shape) -> Option<Penetration> {
+        match (self, other) {
+            (Shape::Polygon(a), Shape::Polygon(b)) => penetration(a, b),
+            (Shape::Polygon(a), Shape::Circle(b)) => polygon_circle_penetration(a, b),
+            (Shape::Circle(a), Shape::Polygon(b)) => {
+                polygon_circle_penetration(b, a).map(|p| Penetration {
+                    depth: p.depth,
+                    normal: p.normal * -1.0,
+                })
+            }
+            (Shape::Circle(a), Shape::Circle(b)) => circles_penetration(a, b),
+            (Shape::Edge(edge), _) => {
+                edge_penetration(edge, &other.core(), other.radius(), other.centroid())
+            }
+            (_, Shape::Edge(edge)) => {
+                edge_penetration(edge, &self.core(), self.radius(), self.centroid()).map(|p| {
+                    Penetration {
+                        depth: p.depth,
+                        normal: p.normal * -1.0,
+                    }
+                })
+            }
+            _ => rounded_penetration(&self.core(), self.radius(), &other.core(), other.radius()),
+        }
+    }
+
+    /// Returns the minimum translation vector that, applied to `other`,
+    /// separates it from `self`. Returns `None` if they don't overlap.
+    pub fn mtv(&self, other: &Shape) -> Option<Vector> {
+        // assure separation
+        return self.penetration(other).map(|p| p.normal * (p.depth + 1e-9));
+    }
+
+    /// Builds the contact manifold of two shapes, or returns `None` if they
+    /// are farther apart than `CONTACT_MARGIN`.
+    pub fn contact_manifold(&self, other: &Shape) -> Option<ContactManifold> {
+        match (self, other) {
+            (Shape::Polygon(a), Shape::Polygon(b)) => contact_manifold(a, b),
+            (Shape::Polygon(a), Shape::Circle(b)) => polygon_circle_contact_manifold(a, b),
+            (Shape::Circle(a), Shape::Polygon(b)) => {
+                polygon_circle_contact_manifold(b, a).map(|m| m.flipped())
+            }
+            (Shape::Circle(a), Shape::Circle(b)) => circles_contact_manifold(a, b),
+            (Shape::Edge(edge), _) => {
+                edge_contact_manifold(edge, &other.core(), other.radius(), other.centroid())
+            }
+            (_, Shape::Edge(edge)) => {
+                edge_contact_manifold(edge, &self.core(), self.radius(), self.centroid())
+                    .map(|m| m.flipped())
+            }
+            _ => {
+                rounded_contact_manifold(&self.core(), self.radius(), &other.core(), other.radius())
+            }
+        }
+    }
+
+    // Any shape is a convex core inflated by a radius
+    pub(crate) fn core(&self) -> Vec<Point> {
+        match self {
+            Shape::Polygon(polygon) => polygon.vertices.clone(),
+            Shape::Circle(circle) => vec![circle.center],
+            Shape::Capsule(capsule) => vec![capsule.segment.a, capsule.segment.b],
+            Shape::RoundedPolygon(rounded) => rounded.polygon.vertices.clone(),
+            Shape::Edge(edge) => vec![edge.segment.a, edge.segment.b],
+        }
+    }
+
+    pub(crate) fn radius(&self) -> f64 {
+        match self {
+            Shape::Polygon(_) => 0.0,
+            Shape::Circle(circle) => circle.radius,
+            Shape::Capsule(capsule) => capsule.radius,
+            Shape::RoundedPolygon(rounded) => rounded.radius,
+            Shape::Edge(_) => 0.0,
+        }
+    }
+
+    // The shape moved from the local space of `transform` to world space
+    pub fn transformed(&self, transform: &Transform) -> Shape {
+        let mut shape = self.clone();
+        shape.rotate_about(Point::zero(), transform.rotation);
+        shape.move_xy(transform.translation);
+        return shape;
+    }
+
+    // Mutators
+    pub fn move_xy(&mut self, offset: Vector) {
+        match self {
+            Shape::Polygon(polygon) => polygon.move_xy(offset),
+            Shape::Circle(circle) => circle.move_xy(offset),
+            Shape::Capsule(capsule) => capsule.move_xy(offset),
+            Shape::RoundedPolygon(rounded) => rounded.move_xy(offset),
+            Shape::Edge(edge) => edge.move_xy(offset),
+        }
+    }
+    pub fn move_x(&mut self, offset_x: f64) {
+        self.move_xy(Vector::new(offset_x, 0.0));
+    }
+    pub fn move_y(&mut self, offset_y: f64) {
+        self.move_xy(Vector::new(0.0, offset_y));
+    }
+    // Around the centroid
+    pub fn rotate(&mut self, angle: f64) {
+        self.rotate_about(self.centroid(), angle);
+    }
+    pub fn rotate_about(&mut self, center: Point, angle: f64) {
+        match self {
+            Shape::Polygon(polygon) => polygon.rotate_about(center, angle),
+            Shape::Circle(circle) => circle.rotate_about(center, angle),
+            Shape::Capsule(capsule) => capsule.rotate_about(center, angle),
+            Shape::RoundedPolygon(rounded) => rounded.rotate_about(center, angle),
+            Shape::Edge(edge) => edge.rotate_about(center, angle),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::square;
+    use similar::assert_similar;
+    use similar::Similar;
+
+    #[test]
+    fn accessors() {
+        let circle = Shape::from(Circle::new(Point::new(2.0, 3.0), 1.0));
+        assert_similar!(circle.width(), 2.0);
+        assert_similar!(circle.left(), 1.0);
+        assert_similar!(circle.top(), 4.0);
+        let square = Shape::from(square(0.0, 0.0, 1.0));
+        assert_similar!(square.width(), 1.0);
+        assert_similar!(square.top(), 1.0);
+        assert_similar!(square.centroid(), Point::new(0.5, 0.5));
+    }
+
+    #[test]
+    fn penetration_both_orders() {
+        let square = Shape::from(square(0.0, 0.0, 1.0));
+        let ball = Shape::from(Circle::new(Point::new(1.25, 0.5), 0.5));
+        let expected = Penetration {
+            depth: 0.25,
+            normal: Vector::new(1.0, 0.0),
+        };
+        assert_similar!(square.penetration(&ball), Some(expected));
+        let expected = Penetration {
+            depth: 0.25,
+            normal: Vector::new(-1.0, 0.0),
+        };
+        assert_similar!(ball.penetration(&square), Some(expected));
+    }
+
+    #[test]
+    fn mtv_separates() {
+        let pairs = [
+            (
+                Shape::from(square(0.0, 0.0, 1.0)),
+                Shape::from(Circle::new(Point::new(0.5, 1.2), 0.5)),
+            ),
+            (
+                Shape::from(Circle::new(Point::new(0.5, 1.2), 0.5)),
+                Shape::from(square(0.0, 0.0, 1.0)),
+            ),
+            (
+                Shape::from(Circle::new(Point::new(0.0, 0.0), 1.0)),
+                Shape::from(Circle::new(Point::new(0.3, 1.0), 1.0)),
+            ),
+        ];
+        for (a, mut b) in pairs {
+            assert!(a.overlaps(&b));
+            b.move_xy(a.mtv(&b).unwrap());
+            assert!(!a.overlaps(&b));
+        }
+    }
+
+    #[test]
+    fn contact_manifold_flipped() {
+        let square = Shape::from(square(0.0, 0.0, 1.0));
+        let ball = Shape::from(Circle::new(Point::new(0.5, 1.4), 0.5));
+        let manifold = ball.contact_manifold(&square).unwrap();
+        assert_similar!(manifold.normal, Vector::new(0.0, -1.0));
+        assert_similar!(manifold.points[0].depth, 0.1);
+        assert_eq!(manifold.points[0].id.b, crate::Feature::Edge(2));
+    }
+
+    #[test]
+    fn rotate_circle_keeps_center() {
+        let mut ball = Shape::from(Circle::new(Point::new(1.0, 2.0), 0.5));
+        ball.rotate(1.0);
+        assert_similar!(ball.centroid(), Point::new(1.0, 2.0));
+    }
+
+    #[test]
+    fn capsule_against_every_shape() {
+        let capsule = Shape::from(Capsule::new(
+            crate::Segment::from_points(Point::new(0.0, 1.2), Point::new(2.0, 1.2)),
+            0.5,
+        ));
+        let others = [
+            Shape::from(square(0.0, 0.0, 1.0)),
+            Shape::from(Circle::new(Point::new(2.5, 1.5), 0.5)),
+            Shape::from(Capsule::new(
+                crate::Segment::from_points(Point::new(1.0, 0.0), Point::new(1.0, 2.0)),
+                0.25,
+            )),
+        ];
+        for other in others.iter() {
+            let mut moved = other.clone();
+            assert!(capsule.overlaps(&moved));
+            assert!(capsule.contact_manifold(&moved).is_some());
+            moved.move_xy(capsule.mtv(&moved).unwrap());
+            assert!(!capsule.overlaps(&moved));
+        }
+        let expected = Penetration {
+            depth: 0.3,
+            normal: Vector::new(0.0, -1.0),
+        };
+        assert_similar!(
+            capsule.penetration(&Shape::from(square(0.0, 0.0, 1.0))),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn rounded_polygon_against_every_shape() {
+        let rounded = Shape::from(crate::RoundedPolygon::new(
+            Polygon::new(&vec![
+                Point::new(0.0, 0.5),
+                Point::new(1.0, 0.5),
+                Point::new(1.0, 1.5),
+                Point::new(0.0, 1.5),
+            ]),
+            0.25,
+        ));
+        let others = [
+            Shape::from(square(0.0, 0.0, 1.0)),
+            Shape::from(Circle::new(Point::new(1.5, 2.0), 0.5)),
+            Shape::from(Capsule::new(
+                crate::Segment::from_points(Point::new(0.5, 1.6), Point::new(0.5, 4.0)),
+                0.25,
+            )),
+            rounded.clone(),
+        ];
+        for other in others.iter() {
+            let mut moved = other.clone();
+            assert!(rounded.overlaps(&moved));
+            assert!(rounded.contact_manifold(&moved).is_some());
+            moved.move_xy(rounded.mtv(&moved).unwrap());
+            assert!(!rounded.overlaps(&moved));
+        }
+        let expected = Penetration {
+            depth: 0.75,
+            normal: Vector::new(0.0, -1.0),
+        };
+        assert_similar!(
+            rounded.penetration(&Shape::from(square(0.0, 0.0, 1.0))),
+            Some(expected)
+        );
+    }
+}
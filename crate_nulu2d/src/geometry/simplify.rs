@@ -0,0 +1,239 @@
+use crate::Point;
+use crate::Segment;
+
+// Outlines are never reduced below a triangle
+const MIN_VERTICES: usize = 3;
+
+// Merges runs of consecutive vertices closer than `tolerance` to each other
+// (including the last and the first), keeping the first vertex of each run.
+// Returns the indices of the kept vertices.
+pub(crate) fn weld(vertices: &[Point], tolerance: f64) -> Vec<usize> {
+    let mut kept: Vec<usize> = Vec::with_capacity(vertices.len());
+    for i in 0..vertices.len() {
+        let repeated = match kept.last() {
+            Some(last) => vertices[*last].distance(vertices[i]) <= tolerance,
+            None => false,
+        };
+        if !repeated {
+            kept.push(i);
+        }
+    }
+    while kept.len() > 1 && vertices[kept[0]].distance(vertices[*kept.last().unwrap()]) <= tolerance
+    {
+        kept.pop();
+    }
+    return with_minimum(kept, vertices.len());
+}
+
+// Drops the vertices lying within `tolerance` of the segment joining their
+// neighbours, one at a time so each is tested against the remaining ones.
+// Repeated vertices are dropped too, but not spikes (whose tip is away from
+// the segment).
+// Returns the indices of the kept vertices.
+pub(crate) fn remove_collinear(vertices: &[Point], tolerance: f64) -> Vec<usize> {
+    let mut kept: Vec<usize> = (0..vertices.len()).collect();
+    // Dropping a vertex changes the neighbours of the ones before it, so
+    // this goes around until nothing changes
+    let mut changed = true;
+    while changed {
+        changed = false;
+        let mut i = 0;
+        while kept.len() > MIN_VERTICES && i < kept.len() {
+            let len = kept.len();
+            let previous = vertices[kept[(i + len - 1) % len]];
+            let next = vertices[kept[(i + 1) % len]];
+            let vertex = vertices[kept[i]];
+            let chord = Segment::from_points(previous, next);
+            if chord.closest_point(vertex).distance(vertex) <= tolerance {
+                kept.remove(i);
+                changed = true;
+            } else {
+                i += 1;
+            }
+        }
+    }
+    return kept;
+}
+
+// Ramer-Douglas-Peucker on a closed outline: the outline is cut at the
+// first vertex and the one furthest from it, and each half is simplified so
+// that no dropped vertex is further than `tolerance` from the result.
+// Returns the indices of the kept vertices.
+pub(crate) fn ramer_douglas_peucker(vertices: &[Point], tolerance: f64) -> Vec<usize> {
+    let len = vertices.len();
+    if len <= MIN_VERTICES {
+        return (0..len).collect();
+    }
+    let furthest = (1..len)
+        .max_by(|i, j| {
+            let distance_i = vertices[*i].distance(vertices[0]);
+            distance_i
+                .partial_cmp(&vertices[*j].distance(vertices[0]))
+                .unwrap()
+        })
+        .unwrap();
+
+    let mut keep = vec![false; len];
+    keep[0] = true;
+    keep[furthest] = true;
+    simplify_chain(vertices, 0, furthest, tolerance, &mut keep);
+    simplify_chain(vertices, furthest, len, tolerance, &mut keep);
+
+    let kept: Vec<usize> = (0..len).filter(|i| keep[*i]).collect();
+    return with_minimum(kept, len);
+}
+
+// Keeps the vertex of the open chain `first..=last` (wrapping around) that is
+// furthest from the chord, if it is further than `tolerance`, and recurses on
+// both sides of it
+fn simplify_chain(
+    vertices: &[Point],
+    first: usize,
+    last: usize,
+    tolerance: f64,
+    keep: &mut [bool],
+) {
+    if last <= first + 1 {
+        return;
+    }
+    let len = vertices.len();
+    let chord = Segment::from_points(vertices[first], vertices[last % len]);
+    let distance = |i: usize| chord.closest_point(vertices[i]).distance(vertices[i]);
+    let furthest = (first + 1..last)
+        .max_by(|i, j| distance(*i).partial_cmp(&distance(*j)).unwrap())
+        .unwrap();
+    if distance(furthest) > tolerance {
+        keep[furthest] = true;
+        simplify_chain(vertices, first, furthest, tolerance, keep);
+        simplify_chain(vertices, furthest, last, tolerance, keep);
+    }
+}
+
+// When too few vertices are left, gives back the original ones
+fn with_minimum(kept: Vec<usize>, len: usize) -> Vec<usize> {
+    if kept.len() < MIN_VERTICES {
+        return (0..len).collect();
+    }
+    return kept;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::square;
+    use crate::Point;
+    use crate::Polygon;
+    use similar::assert_similar;
+    use similar::Similar;
+
+    // The square, traced with extra vertices along its sides
+    fn traced_square(noise: f64) -> Polygon {
+        let mut vertices = Vec::new();
+        let corners = square(0.0, 0.0, 1.0).vertices;
+        for (i, corner) in corners.iter().enumerate() {
+            let next = corners[(i + 1) % 4];
+            let side = next - *corner;
+            let normal = side.perp();
+            for step in 0..10 {
+                let offset = if step % 2 == 0 { 0.0 } else { noise };
+                vertices.push(*corner + side * (step as f64 / 10.0) + normal * offset);
+            }
+        }
+        return Polygon::new(&vertices);
+    }
+
+    #[test]
+    fn weld_vertices() {
+        let p = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1e-7),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 1.0),
+            Point::new(1e-7, 0.0),
+        ]);
+        let (welded, dropped) = p.weld_vertices(1e-6);
+        assert_eq!(dropped, 3);
+        assert_similar!(&welded, &square(0.0, 0.0, 1.0));
+
+        // Nothing left to weld
+        let (same, dropped) = square(0.0, 0.0, 1.0).weld_vertices(1e-6);
+        assert_eq!(dropped, 0);
+        assert_eq!(same, square(0.0, 0.0, 1.0));
+
+        // Never below a triangle
+        let tiny = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(1e-7, 0.0),
+            Point::new(0.0, 1e-7),
+        ]);
+        assert_eq!(tiny.weld_vertices(1e-6), (tiny.clone(), 0));
+    }
+
+    #[test]
+    fn remove_collinear_vertices() {
+        let (simplified, dropped) = traced_square(0.0).remove_collinear_vertices(1e-9);
+        assert_eq!(dropped, 36);
+        assert_similar!(&simplified, &square(0.0, 0.0, 1.0));
+
+        // Out of tolerance
+        let (noisy, dropped) = traced_square(0.01).remove_collinear_vertices(1e-3);
+        assert_eq!(dropped, 0);
+        assert_eq!(noisy.vertices.len(), 40);
+
+        // Repeated vertices are collinear too
+        let p = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(0.5, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 1.0),
+        ]);
+        let (simplified, dropped) = p.remove_collinear_vertices(1e-9);
+        assert_eq!(dropped, 2);
+        assert_similar!(&simplified, &square(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn simplify() {
+        let (simplified, dropped) = traced_square(0.01).simplify(0.05);
+        assert_eq!(dropped, 36);
+        assert_similar!(&simplified, &square(0.0, 0.0, 1.0));
+
+        // Small tolerances keep the bumps
+        let (detailed, dropped) = traced_square(0.01).simplify(0.005);
+        assert_eq!(dropped, 0);
+        assert_eq!(detailed.vertices.len(), 40);
+    }
+
+    #[test]
+    fn simplify_keeps_the_shape() {
+        // A circle with many vertices
+        let circle = Polygon::new(
+            &(0..200)
+                .map(|i| Point::from_polar(i as f64 * std::f64::consts::PI / 100.0, 10.0))
+                .collect(),
+        );
+        let (simplified, dropped) = circle.simplify(0.1);
+        assert!(dropped > 150, "{}", dropped);
+        assert_eq!(simplified.vertices.len() + dropped, 200);
+        for v in circle.vertices.iter() {
+            let distance = simplified
+                .segments()
+                .iter()
+                .map(|s| s.closest_point(*v).distance(*v))
+                .fold(f64::INFINITY, f64::min);
+            assert!(distance <= 0.1);
+        }
+
+        // Too flat to keep anything, so nothing is dropped
+        let sliver = Polygon::new(&vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(2.0, 0.01),
+            Point::new(1.0, 0.01),
+        ]);
+        assert_eq!(sliver.simplify(0.1), (sliver.clone(), 0));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::Aabb;
+use crate::Capsule;
+use crate::Circle;
+use crate::Edge;
+use crate::Point;
+use crate::Polygon;
+use crate::RoundedPolygon;
+use crate::Segment;
+use crate::Shape;
+use crate::Vector;
+
+/// A convex shape described by its support function, which is all GJK and
+/// EPA need (see `gjk_distance` and `epa_penetration`). Implement it for
+/// shapes that aren't worth turning into vertex lists, to collide them with
+/// the built-in ones.
+pub trait SupportMap {
+    /// The point of the shape furthest along `direction` (which may not be a
+    /// unit vector).
+    fn support(&self, direction: Vector) -> Point;
+
+    /// The bounding box of the shape. Defaults to the support points along
+    /// both axes.
+    fn aabb(&self) -> Aabb {
+        let min = Point::new(
+            self.support(Vector::new(-1.0, 0.0)).x,
+            self.support(Vector::new(0.0, -1.0)).y,
+        );
+        let max = Point::new(
+            self.support(Vector::new(1.0, 0.0)).x,
+            self.support(Vector::new(0.0, 1.0)).y,
+        );
+        return Aabb::new(min, max);
+    }
+}
+
+impl<T: SupportMap + ?Sized> SupportMap for &T {
+    fn support(&self, direction: Vector) -> Point {
+        return (**self).support(direction);
+    }
+    fn aabb(&self) -> Aabb {
+        return (**self).aabb();
+    }
+}
+
+impl<T: SupportMap + ?Sized> SupportMap for Box<T> {
+    fn support(&self, direction: Vector) -> Point {
+        return (**self).support(direction);
+    }
+    fn aabb(&self) -> Aabb {
+        return (**self).aabb();
+    }
+}
+
+impl SupportMap for Polygon {
+    fn support(&self, direction: Vector) -> Point {
+        return *self
+            .vertices
+            .iter()
+            .max_by(|p, q| (**p * direction).partial_cmp(&(**q * direction)).unwrap())
+            .unwrap();
+    }
+    fn aabb(&self) -> Aabb {
+        return Polygon::aabb(self);
+    }
+}
+
+impl SupportMap for Circle {
+    fn support(&self, direction: Vector) -> Point {
+        if direction.norm() == 0.0 {
+            return self.center;
+        }
+        return self.center + direction.unit() * self.radius;
+    }
+    fn aabb(&self) -> Aabb {
+        return Circle::aabb(self);
+    }
+}
+
+impl SupportMap for Segment {
+    fn support(&self, direction: Vector) -> Point {
+        return if self.a * direction >= self.b * direction {
+            self.a
+        } else {
+            self.b
+        };
+    }
+}
+
+impl SupportMap for Capsule {
+    fn support(&self, direction: Vector) -> Point {
+        let end = self.segment.support(direction);
+        return Circle::new(end, self.radius).support(direction);
+    }
+    fn aabb(&self) -> Aabb {
+        return Capsule::aabb(self);
+    }
+}
+
+impl SupportMap for RoundedPolygon {
+    fn support(&self, direction: Vector) -> Point {
+        let vertex = self.polygon.support(direction);
+        return Circle::new(vertex, self.radius).support(direction);
+    }
+    fn aabb(&self) -> Aabb {
+        return RoundedPolygon::aabb(self);
+    }
+}
+
+impl SupportMap for Edge {
+    fn support(&self, direction: Vector) -> Point {
+        return self.segment.support(direction);
+    }
+    fn aabb(&self) -> Aabb {
+        return Edge::aabb(self);
+    }
+}
+
+impl SupportMap for Shape {
+    fn support(&self, direction: Vector) -> Point {
+        match self {
+            Shape::Polygon(polygon) => polygon.support(direction),
+            Shape::Circle(circle) => circle.support(direction),
+            Shape::Capsule(capsule) => capsule.support(direction),
+            Shape::RoundedPolygon(rounded) => rounded.support(direction),
+            Shape::Edge(edge) => edge.support(direction),
+        }
+    }
+    fn aabb(&self) -> Aabb {
+        return Shape::aabb(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epa_penetration;
+    use crate::gjk_distance;
+    use crate::gjk_overlaps;
+    use similar::assert_similar;
+    use similar::Similar;
+
+    // A procedural shape that has no vertex list
+    struct Ellipse {
+        center: Point,
+        radii: Vector,
+    }
+
+    impl SupportMap for Ellipse {
+        fn support(&self, direction: Vector) -> Point {
+            // Scale the direction into the unit circle space and back
+            let scaled = Vector::new(direction.x * self.radii.x, direction.y * self.radii.y);
+            let unit = scaled.unit();
+            return self.center + Vector::new(unit.x * self.radii.x, unit.y * self.radii.y);
+        }
+    }
+
+    fn ellipse() -> Ellipse {
+        Ellipse {
+            center: Point::new(0.0, 0.0),
+            radii: Vector::new(3.0, 1.0),
+        }
+    }
+
+    #[test]
+    fn default_aabb() {
+        let aabb = ellipse().aabb();
+        assert_similar!(aabb.min, Point::new(-3.0, -1.0));
+        assert_similar!(aabb.max, Point::new(3.0, 1.0));
+    }
+
+    #[test]
+    fn custom_shape_against_builtin_shapes() {
+        let ellipse = ellipse();
+        let circle = Circle::new(Point::new(5.0, 0.0), 1.0);
+        let closest = gjk_distance(&ellipse, &circle).unwrap();
+        assert_similar!(closest.distance, 1.0, 1e-6);
+        assert_similar!(closest.point_a, Point::new(3.0, 0.0), 1e-4);
+
+        let box_on_top = Polygon::new(&vec![
+            Point::new(-1.0, 0.5),
+            Point::new(1.0, 0.5),
+            Point::new(1.0, 2.0),
+            Point::new(-1.0, 2.0),
+        ]);
+        assert!(gjk_overlaps(&ellipse, &box_on_top));
+        let penetration = epa_penetration(&ellipse, &box_on_top).unwrap();
+        assert_similar!(penetration.depth, 0.5, 1e-3);
+        assert_similar!(penetration.normal, Vector::new(0.0, 1.0), 1e-2);
+
+        // Through trait objects, e.g. when mixing several custom shapes
+        let shapes: Vec<Box<dyn SupportMap>> = vec![Box::new(ellipse), Box::new(circle)];
+        assert!(!gjk_overlaps(&shapes[0], &shapes[1]));
+        assert!(gjk_overlaps(&shapes[0], &Shape::from(box_on_top)));
+    }
+}
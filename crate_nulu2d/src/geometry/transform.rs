@@ -0,0 +1,99 @@
+use crate::Point;
+use crate::Vector;
+
+use similar::Similar;
+use similar_derive::Similar;
+
+/// A rotation around the origin followed by a translation, to go from the
+/// local space of a shape to world space.
+#[derive(Copy, Clone, Debug, PartialEq, Similar)]
+pub struct Transform {
+    pub translation: Vector,
+    pub rotation: f64,
+}
+
+impl Transform {
+    pub fn new(translation: Vector, rotation: f64) -> Transform {
+        Transform {
+            translation,
+            rotation,
+        }
+    }
+
+    pub fn identity() -> Transform {
+        Transform::new(Vector::zero(), 0.0)
+    }
+
+    // From local to world space
+    pub fn apply(&self, point: Point) -> Point {
+        return point.rotated_by(self.rotation) + self.translation;
+    }
+
+    // Directions aren't translated
+    pub fn apply_vector(&self, vector: Vector) -> Vector {
+        return vector.rotated_by(self.rotation);
+    }
+
+    // From world to local space
+    pub fn inverse_apply(&self, point: Point) -> Point {
+        return (point - self.translation).rotated_by(-self.rotation);
+    }
+
+    pub fn inverse_apply_vector(&self, vector: Vector) -> Vector {
+        return vector.rotated_by(-self.rotation);
+    }
+
+    pub fn inverse(&self) -> Transform {
+        return Transform {
+            translation: (self.translation * -1.0).rotated_by(-self.rotation),
+            rotation: -self.rotation,
+        };
+    }
+
+    // Applies `self`, then `other`
+    pub fn then(&self, other: &Transform) -> Transform {
+        return Transform {
+            translation: other.apply(self.translation),
+            rotation: self.rotation + other.rotation,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use similar::assert_similar;
+    use std::f64::consts;
+
+    #[test]
+    fn apply() {
+        let transform = Transform::new(Vector::new(1.0, 2.0), consts::PI / 2.0);
+        assert_similar!(transform.apply(Point::new(1.0, 0.0)), Point::new(1.0, 3.0));
+        assert_similar!(
+            transform.apply_vector(Vector::new(1.0, 0.0)),
+            Vector::new(0.0, 1.0)
+        );
+        assert_similar!(
+            transform.inverse_apply(Point::new(1.0, 3.0)),
+            Point::new(1.0, 0.0)
+        );
+        assert_similar!(
+            transform.inverse_apply_vector(Vector::new(0.0, 1.0)),
+            Vector::new(1.0, 0.0)
+        );
+        assert_similar!(
+            Transform::identity().apply(Point::new(4.0, 5.0)),
+            Point::new(4.0, 5.0)
+        );
+    }
+
+    #[test]
+    fn inverse_and_then() {
+        let a = Transform::new(Vector::new(1.0, 2.0), 0.5);
+        let b = Transform::new(Vector::new(-3.0, 0.5), 2.0);
+        let point = Point::new(0.3, -0.7);
+        assert_similar!(a.inverse().apply(a.apply(point)), point);
+        assert_similar!(a.then(&a.inverse()), Transform::identity());
+        assert_similar!(a.then(&b).apply(point), b.apply(a.apply(point)));
+    }
+}
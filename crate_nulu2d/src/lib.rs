@@ -5,9 +5,57 @@
 
 mod geometry;
 
+pub use crate::geometry::collision::circles_penetration;
+pub use crate::geometry::collision::contains_point;
+pub use crate::geometry::collision::intersection;
+pub use crate::geometry::collision::mtv;
+pub use crate::geometry::collision::overlaps;
+pub use crate::geometry::collision::parametric_intersection;
+pub use crate::geometry::collision::penetration;
+pub use crate::geometry::collision::polygon_circle_penetration;
+pub use crate::geometry::contact::circles_contact_manifold;
+pub use crate::geometry::contact::contact_manifold;
+pub use crate::geometry::contact::polygon_circle_contact_manifold;
+pub use crate::geometry::gjk::epa_penetration;
+pub use crate::geometry::gjk::gjk_distance;
+pub use crate::geometry::gjk::gjk_overlaps;
+pub use crate::geometry::Aabb;
+pub use crate::geometry::Capsule;
+pub use crate::geometry::Chain;
+pub use crate::geometry::Circle;
+pub use crate::geometry::ClosestPoints;
+pub use crate::geometry::ContactManifold;
+pub use crate::geometry::ContactPoint;
+pub use crate::geometry::Edge;
+pub use crate::geometry::Feature;
+pub use crate::geometry::FeatureId;
+pub use crate::geometry::MassData;
+pub use crate::geometry::Penetration;
 pub use crate::geometry::Point;
 pub use crate::geometry::Polygon;
+pub use crate::geometry::PolygonError;
+pub use crate::geometry::PolygonWithHoles;
+pub use crate::geometry::Ray;
+pub use crate::geometry::RayHit;
+pub use crate::geometry::RoundedPolygon;
 pub use crate::geometry::Segment;
+pub use crate::geometry::Shape;
+pub use crate::geometry::SupportMap;
+pub use crate::geometry::Transform;
 pub use crate::geometry::Vector;
 
 mod physics;
+
+pub use crate::physics::Body;
+pub use crate::physics::BroadPhase;
+pub use crate::physics::BruteForce;
+pub use crate::physics::DynamicTree;
+pub use crate::physics::Separation;
+pub use crate::physics::ShapeCastHit;
+pub use crate::physics::SpatialGrid;
+pub use crate::physics::StepStats;
+pub use crate::physics::SweepAndPrune;
+pub use crate::physics::World;
+
+#[cfg(test)]
+mod fixtures;
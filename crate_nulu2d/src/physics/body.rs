@@ -1,42 +1,135 @@
+use crate::Aabb;
+use crate::MassData;
 use crate::Point;
-use crate::Polygon;
+use crate::Shape;
+use crate::Transform;
 use crate::Vector;
 
+/// A shape with mass and velocity. The shape is kept in the local space of
+/// the body, whose origin is the center of mass, and placed in the world by
+/// its transform. The world-space shape is cached and rebuilt from the local
+/// one whenever the transform changes, so moving a body around doesn't
+/// accumulate rounding errors.
 pub struct Body {
-    pub shape: Polygon,
+    local_shape: Shape,
+    transform: Transform,
+    shape: Shape,
     pub mass: f64,
+    pub inertia: f64,
     pub friction: f64,
     pub velocity: Vector,
+    pub angular_velocity: f64,
     pub frictionless: bool,
     pub gravityless: bool,
 }
 
 impl Body {
+    // The shape is given in world space. Edges have no area to spread a mass
+    // over, so bodies made of them must be static (infinite mass), see
+    // `World::add_static_body` and `World::add_chain`; this panics otherwise.
     pub fn new(
-        shape: Polygon,
+        shape: impl Into<Shape>,
         mass: f64,
         friction: f64,
         frictionless: bool,
         gravityless: bool,
     ) -> Body {
+        let shape = shape.into();
+        if let Shape::Edge(_) = shape {
+            assert!(
+                mass == f64::INFINITY,
+                "Edge bodies must be static ({} mass used)",
+                mass
+            );
+        }
+        // Static bodies don't rotate, whether their shape has an area or not
+        let inertia = if mass == f64::INFINITY {
+            f64::INFINITY
+        } else {
+            shape.moment_of_inertia(mass / shape.area())
+        };
+        let transform = Transform::new(shape.centroid(), 0.0);
+        let mut local_shape = shape.clone();
+        local_shape.move_xy(transform.translation * -1.0);
         Body {
+            local_shape,
+            transform,
             shape,
             mass,
+            inertia,
             friction,
             velocity: Vector::new(0.0, 0.0),
+            angular_velocity: 0.0,
             frictionless,
             gravityless,
         }
     }
 
+    pub fn from_density(
+        shape: impl Into<Shape>,
+        density: f64,
+        friction: f64,
+        frictionless: bool,
+        gravityless: bool,
+    ) -> Body {
+        let shape = shape.into();
+        let mass = shape.area() * density;
+        Body::new(shape, mass, friction, frictionless, gravityless)
+    }
+
+    pub fn mass_data(&self) -> MassData {
+        MassData {
+            mass: self.mass,
+            center: self.center(),
+            inertia: self.inertia,
+        }
+    }
+
+    // Shape and placement
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+    // Centered on the center of mass
+    pub fn local_shape(&self) -> &Shape {
+        &self.local_shape
+    }
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+        self.shape = self.local_shape.transformed(&transform);
+    }
+    pub fn angle(&self) -> f64 {
+        self.transform.rotation
+    }
+
+    // Conversions between local and world space
+    pub fn to_world(&self, point: Point) -> Point {
+        self.transform.apply(point)
+    }
+    pub fn to_local(&self, point: Point) -> Point {
+        self.transform.inverse_apply(point)
+    }
+    pub fn vector_to_world(&self, vector: Vector) -> Vector {
+        self.transform.apply_vector(vector)
+    }
+    pub fn vector_to_local(&self, vector: Vector) -> Vector {
+        self.transform.inverse_apply_vector(vector)
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        self.shape.aabb()
+    }
     pub fn width(&self) -> f64 {
         self.shape.width()
     }
     pub fn height(&self) -> f64 {
         self.shape.height()
     }
+    // Center of mass
     pub fn center(&self) -> Point {
-        self.shape.center()
+        self.transform.translation
     }
     pub fn left(&self) -> f64 {
         self.shape.left()
@@ -52,13 +145,38 @@ impl Body {
     }
 
     pub fn move_xy(&mut self, offset: Vector) {
-        self.shape.move_xy(offset);
+        // Resting bodies don't need their shape rebuilt
+        if offset == Vector::zero() {
+            return;
+        }
+        let mut transform = self.transform;
+        transform.translation += offset;
+        self.set_transform(transform);
     }
     pub fn move_x(&mut self, offset_x: f64) {
-        self.shape.move_x(offset_x);
+        self.move_xy(Vector::new(offset_x, 0.0));
     }
     pub fn move_y(&mut self, offset_y: f64) {
-        self.shape.move_y(offset_y);
+        self.move_xy(Vector::new(0.0, offset_y));
+    }
+    // Around the center of mass
+    pub fn rotate(&mut self, angle: f64) {
+        let mut transform = self.transform;
+        transform.rotation += angle;
+        self.set_transform(transform);
+    }
+
+    // Velocity of a point of the body, taking its rotation into account
+    pub fn velocity_at(&self, point: Point) -> Vector {
+        let r = point - self.center();
+        return self.velocity + Vector::new(-r.y, r.x) * self.angular_velocity;
+    }
+
+    // Applies an impulse at a point of the body
+    pub fn apply_impulse(&mut self, impulse: Vector, point: Point) {
+        let r = point - self.center();
+        self.velocity += impulse / self.mass;
+        self.angular_velocity += (r ^ impulse) / self.inertia;
     }
 
     /*
@@ -90,3 +208,175 @@ impl Body {
     //   return floor_normal
     // end
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::rectangle;
+    use crate::fixtures::square;
+    use crate::Circle;
+    use crate::Edge;
+    use crate::Polygon;
+    use crate::Segment;
+    use similar::assert_similar;
+    use similar::Similar;
+
+    #[test]
+    fn inertia() {
+        let body = Body::new(rectangle(0.0, 0.0, 2.0, 1.0), 3.0, 0.0, false, false);
+        assert_similar!(body.inertia, 3.0 * (4.0 + 1.0) / 12.0);
+
+        let body = Body::new(
+            rectangle(0.0, 0.0, 2.0, 1.0),
+            f64::INFINITY,
+            0.0,
+            false,
+            false,
+        );
+        assert_eq!(body.inertia, f64::INFINITY);
+    }
+
+    #[test]
+    fn edges_are_static() {
+        let segment = Segment::from_points(Point::new(0.0, 0.0), Point::new(2.0, 0.0));
+        let mut body = Body::new(Edge::new(segment), f64::INFINITY, 0.0, false, true);
+        assert_eq!(body.inertia, f64::INFINITY);
+        assert_eq!(body.mass_data(), Edge::new(segment).mass_data(1.0));
+
+        body.apply_impulse(Vector::new(0.0, 2.0), Point::new(2.0, 0.0));
+        assert_eq!(body.velocity, Vector::zero());
+        assert_eq!(body.angular_velocity, 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Edge bodies must be static (1 mass used)")]
+    fn dynamic_edges_are_rejected() {
+        let segment = Segment::from_points(Point::new(0.0, 0.0), Point::new(2.0, 0.0));
+        Body::new(Edge::new(segment), 1.0, 0.0, false, false);
+    }
+
+    #[test]
+    fn from_density() {
+        let small = Body::from_density(square(0.0, 0.0, 1.0), 2.0, 0.0, false, false);
+        let big = Body::from_density(square(0.0, 0.0, 2.0), 2.0, 0.0, false, false);
+        assert_similar!(small.mass, 2.0);
+        assert_similar!(big.mass, 8.0);
+        assert_similar!(big.inertia, 8.0 * 8.0 / 12.0);
+    }
+
+    #[test]
+    fn center_of_mass() {
+        let body = Body::new(
+            Polygon::new(&vec![
+                Point::new(0.0, 0.0),
+                Point::new(3.0, 0.0),
+                Point::new(3.0, 1.0),
+                Point::new(0.0, 1.0),
+                Point::new(0.0, 0.5),
+            ]),
+            1.0,
+            0.0,
+            false,
+            false,
+        );
+        let expected = MassData {
+            mass: 1.0,
+            center: Point::new(1.5, 0.5),
+            inertia: (9.0 + 1.0) / 12.0,
+        };
+        assert_similar!(body.mass_data(), expected);
+    }
+
+    #[test]
+    fn rotate() {
+        let mut body = Body::new(square(0.0, 0.0, 2.0), 1.0, 0.0, false, false);
+        body.rotate(std::f64::consts::PI / 4.0);
+        assert_similar!(body.angle(), std::f64::consts::PI / 4.0);
+        assert_similar!(body.width(), 8.0_f64.sqrt());
+        assert_similar!(body.shape().centroid(), Point::new(1.0, 1.0));
+    }
+
+    #[test]
+    fn apply_impulse_off_center() {
+        let mut body = Body::new(square(0.0, 0.0, 2.0), 2.0, 0.0, false, false);
+        body.apply_impulse(Vector::new(0.0, 2.0), Point::new(2.0, 1.0));
+        assert_similar!(body.velocity, Vector::new(0.0, 1.0));
+        assert_similar!(body.angular_velocity, 2.0 / body.inertia);
+        assert_similar!(
+            body.velocity_at(Point::new(1.0, 2.0)),
+            Vector::new(-2.0 / body.inertia, 1.0)
+        );
+    }
+
+    #[test]
+    fn circle_body() {
+        let mut body = Body::from_density(
+            Circle::new(Point::new(1.0, 1.0), 1.0),
+            2.0,
+            0.0,
+            false,
+            false,
+        );
+        assert_similar!(body.mass, 2.0 * std::f64::consts::PI);
+        assert_similar!(body.inertia, body.mass / 2.0);
+        assert_similar!(body.width(), 2.0);
+        assert_similar!(body.left(), 0.0);
+        assert_similar!(body.top(), 2.0);
+
+        body.rotate(1.0);
+        assert_similar!(body.angle(), 1.0);
+        assert_similar!(body.center(), Point::new(1.0, 1.0));
+    }
+
+    #[test]
+    fn local_and_world_space() {
+        let mut body = Body::new(square(0.0, 0.0, 2.0), 1.0, 0.0, false, false);
+        assert_similar!(body.transform().translation, Point::new(1.0, 1.0));
+        assert_similar!(body.local_shape().centroid(), Point::new(0.0, 0.0));
+
+        body.rotate(std::f64::consts::PI / 2.0);
+        body.move_xy(Vector::new(3.0, 0.0));
+        assert_similar!(body.center(), Point::new(4.0, 1.0));
+        assert_similar!(body.to_world(Point::new(1.0, 0.0)), Point::new(4.0, 2.0));
+        assert_similar!(body.to_local(Point::new(4.0, 2.0)), Point::new(1.0, 0.0));
+        assert_similar!(
+            body.vector_to_world(Vector::new(1.0, 0.0)),
+            Vector::new(0.0, 1.0)
+        );
+        assert_similar!(
+            body.vector_to_local(Vector::new(0.0, 1.0)),
+            Vector::new(1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn spinning_does_not_drift() {
+        let mut body = Body::new(rectangle(0.0, 0.0, 3.0, 1.0), 1.0, 0.0, false, false);
+        let before = body.shape().clone();
+        for _ in 0..10000 {
+            body.rotate(std::f64::consts::PI / 5000.0);
+            body.move_xy(Vector::new(1e-3, 0.0));
+        }
+        body.move_xy(Vector::new(-10.0, 0.0));
+        match (body.shape(), &before) {
+            (Shape::Polygon(after), Shape::Polygon(before)) => {
+                assert_similar!(&after.vertices, &before.vertices, 1e-9)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn shared_local_shape() {
+        let template = Body::new(rectangle(0.0, 0.0, 2.0, 1.0), 1.0, 0.0, false, false);
+        let mut copy = Body::new(template.local_shape().clone(), 1.0, 0.0, false, false);
+        copy.set_transform(Transform::new(
+            Vector::new(5.0, 5.0),
+            std::f64::consts::PI / 2.0,
+        ));
+        assert_similar!(copy.inertia, template.inertia);
+        assert_similar!(copy.width(), 1.0);
+        assert_similar!(copy.height(), 2.0);
+        assert_similar!(copy.left(), 4.5);
+    }
+}
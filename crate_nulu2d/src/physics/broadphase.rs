@@ -0,0 +1,348 @@
+use crate::physics::dynamic_tree::DynamicTree;
+use crate::Aabb;
+use std::collections::HashMap;
+
+/// Finds the pairs of bodies whose bounding boxes overlap, so that only those
+/// go through the narrowphase. Implementations may report extra pairs (e.g.
+/// when they fatten the boxes), but never miss an overlapping one.
+pub trait BroadPhase {
+    /// Sets the box of `id`, adding it if needed.
+    fn update(&mut self, id: u32, aabb: Aabb);
+    /// Forgets about `id`, if it was there.
+    fn remove(&mut self, id: u32);
+    /// All the ids, sorted.
+    fn ids(&self) -> Vec<u32>;
+    /// Ids whose boxes overlap `aabb`, sorted.
+    fn query(&self, aabb: &Aabb) -> Vec<u32>;
+    /// Pairs of ids whose boxes overlap, with the smallest id first, sorted.
+    fn pairs(&self) -> Vec<(u32, u32)>;
+}
+
+impl BroadPhase for DynamicTree {
+    fn update(&mut self, id: u32, aabb: Aabb) {
+        DynamicTree::update(self, id, aabb);
+    }
+    fn remove(&mut self, id: u32) {
+        DynamicTree::remove(self, id);
+    }
+    fn ids(&self) -> Vec<u32> {
+        return DynamicTree::ids(self);
+    }
+    fn query(&self, aabb: &Aabb) -> Vec<u32> {
+        return DynamicTree::query(self, aabb);
+    }
+    fn pairs(&self) -> Vec<(u32, u32)> {
+        return DynamicTree::pairs(self);
+    }
+}
+
+fn sorted_ids(boxes: &HashMap<u32, Aabb>) -> Vec<u32> {
+    let mut ids: Vec<u32> = boxes.keys().cloned().collect();
+    ids.sort();
+    return ids;
+}
+
+fn ordered(a: u32, b: u32) -> (u32, u32) {
+    return if a < b { (a, b) } else { (b, a) };
+}
+
+/// Tests every pair of boxes. Only meant as a reference for the others.
+#[derive(Clone, Debug, Default)]
+pub struct BruteForce {
+    boxes: HashMap<u32, Aabb>,
+}
+
+impl BruteForce {
+    pub fn new() -> BruteForce {
+        BruteForce::default()
+    }
+}
+
+impl BroadPhase for BruteForce {
+    fn update(&mut self, id: u32, aabb: Aabb) {
+        self.boxes.insert(id, aabb);
+    }
+    fn remove(&mut self, id: u32) {
+        self.boxes.remove(&id);
+    }
+    fn ids(&self) -> Vec<u32> {
+        return sorted_ids(&self.boxes);
+    }
+    fn query(&self, aabb: &Aabb) -> Vec<u32> {
+        return self
+            .ids()
+            .into_iter()
+            .filter(|id| self.boxes[id].overlaps(aabb))
+            .collect();
+    }
+    fn pairs(&self) -> Vec<(u32, u32)> {
+        let ids = self.ids();
+        let mut pairs = Vec::new();
+        for (i, a) in ids.iter().enumerate() {
+            for b in ids[i + 1..].iter() {
+                if self.boxes[a].overlaps(&self.boxes[b]) {
+                    pairs.push((*a, *b));
+                }
+            }
+        }
+        return pairs;
+    }
+}
+
+/// Keeps the boxes sorted by their left side, so each box is only tested
+/// against the ones starting before it ends. Works best when bodies are
+/// spread along the x axis, like in side-scrolling levels.
+#[derive(Clone, Debug, Default)]
+pub struct SweepAndPrune {
+    boxes: HashMap<u32, Aabb>,
+    // Ids sorted by the left side of their box
+    order: Vec<u32>,
+}
+
+impl SweepAndPrune {
+    pub fn new() -> SweepAndPrune {
+        SweepAndPrune::default()
+    }
+}
+
+impl BroadPhase for SweepAndPrune {
+    fn update(&mut self, id: u32, aabb: Aabb) {
+        if self.boxes.get(&id) == Some(&aabb) {
+            return;
+        }
+        self.remove(id);
+        let boxes = &self.boxes;
+        let index = self
+            .order
+            .partition_point(|other| boxes[other].min.x <= aabb.min.x);
+        self.order.insert(index, id);
+        self.boxes.insert(id, aabb);
+    }
+    fn remove(&mut self, id: u32) {
+        if self.boxes.remove(&id).is_some() {
+            self.order.retain(|other| *other != id);
+        }
+    }
+    fn ids(&self) -> Vec<u32> {
+        return sorted_ids(&self.boxes);
+    }
+    fn query(&self, aabb: &Aabb) -> Vec<u32> {
+        let mut ids: Vec<u32> = self
+            .order
+            .iter()
+            .take_while(|id| self.boxes[id].min.x <= aabb.max.x)
+            .filter(|id| self.boxes[id].overlaps(aabb))
+            .cloned()
+            .collect();
+        ids.sort();
+        return ids;
+    }
+    fn pairs(&self) -> Vec<(u32, u32)> {
+        let mut pairs = Vec::new();
+        for (i, a) in self.order.iter().enumerate() {
+            let aabb = &self.boxes[a];
+            for b in self.order[i + 1..].iter() {
+                let other = &self.boxes[b];
+                if other.min.x > aabb.max.x {
+                    break;
+                }
+                if aabb.overlaps(other) {
+                    pairs.push(ordered(*a, *b));
+                }
+            }
+        }
+        pairs.sort();
+        return pairs;
+    }
+}
+
+/// Spatial hash of square cells, each box being listed in every cell it
+/// touches. Works best when bodies are about the size of a cell; boxes much
+/// bigger than a cell (e.g. a single floor for the whole level) end up in
+/// many cells.
+#[derive(Clone, Debug)]
+pub struct SpatialGrid {
+    pub cell_size: f64,
+    boxes: HashMap<u32, Aabb>,
+    cells: HashMap<(i64, i64), Vec<u32>>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f64) -> SpatialGrid {
+        assert!(cell_size > 0.0, "The cells of a grid must have some size");
+        SpatialGrid {
+            cell_size,
+            boxes: HashMap::new(),
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cells_of(&self, aabb: &Aabb) -> Vec<(i64, i64)> {
+        let cell = |v: f64| (v / self.cell_size).floor() as i64;
+        let mut cells = Vec::new();
+        for x in cell(aabb.min.x)..=cell(aabb.max.x) {
+            for y in cell(aabb.min.y)..=cell(aabb.max.y) {
+                cells.push((x, y));
+            }
+        }
+        return cells;
+    }
+}
+
+impl BroadPhase for SpatialGrid {
+    fn update(&mut self, id: u32, aabb: Aabb) {
+        if self.boxes.get(&id) == Some(&aabb) {
+            return;
+        }
+        self.remove(id);
+        for cell in self.cells_of(&aabb) {
+            self.cells.entry(cell).or_default().push(id);
+        }
+        self.boxes.insert(id, aabb);
+    }
+    fn remove(&mut self, id: u32) {
+        if let Some(aabb) = self.boxes.remove(&id) {
+            for cell in self.cells_of(&aabb) {
+                let ids = self.cells.get_mut(&cell).unwrap();
+                ids.retain(|other| *other != id);
+                if ids.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+    fn ids(&self) -> Vec<u32> {
+        return sorted_ids(&self.boxes);
+    }
+    fn query(&self, aabb: &Aabb) -> Vec<u32> {
+        let mut ids: Vec<u32> = self
+            .cells_of(aabb)
+            .iter()
+            .filter_map(|cell| self.cells.get(cell))
+            .flatten()
+            .filter(|id| self.boxes[id].overlaps(aabb))
+            .cloned()
+            .collect();
+        ids.sort();
+        ids.dedup();
+        return ids;
+    }
+    fn pairs(&self) -> Vec<(u32, u32)> {
+        // Boxes sharing several cells are found once per cell
+        let mut pairs = Vec::new();
+        for ids in self.cells.values() {
+            for (i, a) in ids.iter().enumerate() {
+                for b in ids[i + 1..].iter() {
+                    if self.boxes[a].overlaps(&self.boxes[b]) {
+                        pairs.push(ordered(*a, *b));
+                    }
+                }
+            }
+        }
+        pairs.sort();
+        pairs.dedup();
+        return pairs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+    use crate::Vector;
+
+    // Deterministic pseudo-random numbers in [0, 1)
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> f64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            return (self.0 >> 11) as f64 / (1u64 << 53) as f64;
+        }
+
+        fn aabb(&mut self) -> Aabb {
+            let min = Point::new(self.next() * 50.0 - 25.0, self.next() * 50.0 - 25.0);
+            let size = Vector::new(self.next() * 4.0, self.next() * 4.0);
+            return Aabb::new(min, min + size);
+        }
+    }
+
+    fn all() -> Vec<Box<dyn BroadPhase>> {
+        return vec![
+            Box::new(BruteForce::new()),
+            Box::new(SweepAndPrune::new()),
+            Box::new(SpatialGrid::new(2.0)),
+            Box::new(SpatialGrid::new(0.3)),
+            // Without a margin the tree reports the exact pairs too
+            Box::new(DynamicTree::new(0.0)),
+        ];
+    }
+
+    // Runs the same operations on every broadphase, checking that they agree
+    // with the brute force one after each round
+    fn check_all_agree(rounds: usize, operation: impl Fn(&mut Lcg, &mut dyn BroadPhase)) {
+        let mut broadphases = all();
+        for round in 0..rounds {
+            let mut results = Vec::new();
+            for broadphase in broadphases.iter_mut() {
+                // Same seed for all of them, so they get the same operations
+                let mut rng = Lcg(round as u64);
+                operation(&mut rng, broadphase.as_mut());
+                let query = rng.aabb();
+                results.push((
+                    broadphase.ids(),
+                    broadphase.query(&query),
+                    broadphase.pairs(),
+                ));
+            }
+            for result in results[1..].iter() {
+                assert_eq!(*result, results[0], "round {}", round);
+            }
+        }
+    }
+
+    #[test]
+    fn same_pairs_when_adding() {
+        check_all_agree(20, |rng, broadphase| {
+            for _ in 0..10 {
+                let id = (rng.next() * 100.0) as u32;
+                broadphase.update(id, rng.aabb());
+            }
+        });
+    }
+
+    #[test]
+    fn same_pairs_when_moving_and_removing() {
+        check_all_agree(50, |rng, broadphase| {
+            for id in 0..40 {
+                if rng.next() < 0.2 {
+                    broadphase.remove(id);
+                } else if rng.next() < 0.5 {
+                    broadphase.update(id, rng.aabb());
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn touching_boxes_are_pairs() {
+        for mut broadphase in all() {
+            let a = Aabb::new(Point::new(0.0, 0.0), Point::new(1.0, 1.0));
+            broadphase.update(3, a);
+            broadphase.update(1, a.moved(Vector::new(1.0, 0.0)));
+            broadphase.update(2, a.moved(Vector::new(0.0, 2.5)));
+            assert_eq!(broadphase.pairs(), vec![(1, 3)]);
+            assert_eq!(
+                broadphase.query(&a.moved(Vector::new(0.0, 1.0))),
+                vec![1, 3]
+            );
+            broadphase.remove(3);
+            broadphase.remove(4);
+            assert_eq!(broadphase.ids(), vec![1, 2]);
+            assert_eq!(broadphase.pairs(), vec![]);
+        }
+    }
+}
@@ -1,78 +1,388 @@
+use crate::geometry::collision::penetration;
+use crate::geometry::rounded::minkowski_difference;
+use crate::geometry::rounded::sweep_point;
+use crate::Edge;
 use crate::Point;
 use crate::Polygon;
-use crate::Segment;
+use crate::Shape;
 use crate::Vector;
 
+/// Same as `get_collision_time_and_normal`, for any pair of shapes.
+pub fn get_shapes_collision_time_and_normal(
+    a: &Shape,
+    va: Vector,
+    b: &Shape,
+    vb: Vector,
+) -> Option<(f64, Vector)> {
+    match (a, b) {
+        (Shape::Polygon(pa), Shape::Polygon(pb)) => get_collision_time_and_normal(pa, va, pb, vb),
+        (Shape::Edge(edge), _) => edge_collision(edge, va, b, vb),
+        (_, Shape::Edge(edge)) => edge_collision(edge, vb, a, va).map(|(t, n)| (t, n * -1.0)),
+        _ => rounded_collision(a, va, b, vb),
+    }
+}
+
+/// Returns the time and collision normal at which two convex polygons moving
+/// at constant velocities will collide. The normal points from `pa` towards
+/// `pb`.
+/// If they're already overlapping, time is 0 and the normal is the axis of
+/// least penetration.
+/// If they won't ever collide, `None` is returned.
+/// Uses a modified version of SAT, based on Ron Levin's mails (see README).
+/// This implies that it only works for *convex* polygons.
 pub fn get_collision_time_and_normal(
     pa: &Polygon,
     va: Vector,
     pb: &Polygon,
     vb: Vector,
 ) -> Option<(f64, Vector)> {
-    None
+    let mut max_earlier_time = 0.0;
+    let mut collision_normal = None;
+    let mut min_latter_time = f64::INFINITY;
+
+    let axes = pa
+        .segments()
+        .into_iter()
+        .chain(pb.segments())
+        .map(|s| s.direction())
+        .filter(|d| d.norm() > f64::EPSILON)
+        .map(|d| d.perp().unit());
+
+    for axis in axes {
+        let mut axis = axis;
+
+        // project
+        let (mut mina, mut maxa) = project(pa, axis);
+        let (mut minb, mut maxb) = project(pb, axis);
+        let mut projected_rel_velocity_a = (va - vb) * axis;
+
+        // ensure mina < minb
+        if mina >= minb {
+            axis *= -1.0;
+            let (flipped_mina, flipped_maxa) = (-maxa, -mina);
+            let (flipped_minb, flipped_maxb) = (-maxb, -minb);
+            mina = flipped_mina;
+            maxa = flipped_maxa;
+            minb = flipped_minb;
+            maxb = flipped_maxb;
+            projected_rel_velocity_a = -projected_rel_velocity_a;
+        }
+
+        if minb <= maxa {
+            // overlapped
+            if projected_rel_velocity_a.abs() >= 1e-6 {
+                // will stop overlapping at some point
+                let latter_time = if projected_rel_velocity_a >= 0.0 {
+                    (maxb - mina) / projected_rel_velocity_a
+                } else {
+                    (maxa - minb) / -projected_rel_velocity_a
+                };
+                min_latter_time = f64::min(min_latter_time, latter_time);
+            }
+        } else {
+            // separated
+            if projected_rel_velocity_a >= 1e-6 {
+                // will collide at some point
+                let earlier_time = (minb - maxa) / projected_rel_velocity_a;
+                let latter_time = (maxb - mina) / projected_rel_velocity_a;
+                if earlier_time > max_earlier_time {
+                    max_earlier_time = earlier_time;
+                    collision_normal = Some(axis);
+                }
+                min_latter_time = f64::min(min_latter_time, latter_time);
+            } else {
+                // will never collide
+                return None;
+            }
+        }
+    }
+
+    if max_earlier_time <= min_latter_time {
+        // No collision normal means they're already overlapping
+        let collision_normal =
+            collision_normal.or_else(|| penetration(pa, pb).map(|p| p.normal))?;
+        return Some((max_earlier_time, collision_normal));
+    } else {
+        return None;
+    }
 }
 
-/*
-fn get_collision_time_and_normal(pa : &Polygon, va : Vector, pb : &Polygon, vb : Vector) -> Option<(f64, Vector)> {
-    # Returns the time and collision normal at which two shapes
-    # with certain velocities will collide.
-    # If they're already colliding, time is 0 and normal is nil.
-    # If they won't ever collide, nil is returned.
-    # Uses a modified version of SAT, based on Ron Levin's mails (see
-    # README). This implies that it only works for *convex* polygons.
-    def self.get_collision_time_and_normal(a, velocity_a, b, velocity_b)
-      max_earlier_time = 0
-      collision_normal = nil
-      min_latter_time = INF
-
-      axes = (a.segments + b.segments).map(&:direction)
-                                      .map(&:perp).map(&:unit)
-
-      axes.each do |axis|
-        # project
-        mina, maxa = a.vertex.map{ |v| v * axis }.minmax
-        minb, maxb = b.vertex.map{ |v| v * axis }.minmax
-        projected_rel_velocity_a = (velocity_a - velocity_b) * axis
-
-        # ensure mina < minb
-        unless mina < minb
-          axis = -axis
-          mina, maxa = -maxa, -mina
-          minb, maxb = -maxb, -minb
-          projected_rel_velocity_a = -projected_rel_velocity_a
-        end
-
-        if minb <= maxa # overlapped
-          if projected_rel_velocity_a.abs >= 1e-6 # will stop colliding at some point
-            if projected_rel_velocity_a >= 0
-              latter_time = (maxb - mina) / projected_rel_velocity_a
-            else
-              latter_time = (maxa - minb) / (-projected_rel_velocity_a)
-            end
-            min_latter_time = [min_latter_time, latter_time].min
-          end
-        else # separated
-          if projected_rel_velocity_a >= 1e-6 # will collide at some point
-            earlier_time = (minb - maxa) / projected_rel_velocity_a
-            latter_time = (maxb - mina) / projected_rel_velocity_a
-            if earlier_time > max_earlier_time
-              max_earlier_time = earlier_time
-              collision_normal = axis
-            end
-            if latter_time < min_latter_time
-              min_latter_time = latter_time
-            end
-          else # will never collide
-            return nil
-          end
-        end
-      end
-
-      if max_earlier_time <= min_latter_time
-        [max_earlier_time, collision_normal]
-      else
-        nil
-      end
-    end
-
-    */
+// Time and normal at which two moving rounded shapes collide. The relative
+// motion of b moves the Minkowski difference of the cores, so it's swept
+// against the origin, inflated by both radii.
+fn rounded_collision(a: &Shape, va: Vector, b: &Shape, vb: Vector) -> Option<(f64, Vector)> {
+    if let Some(penetration) = a.penetration(b) {
+        return Some((0.0, penetration.normal));
+    }
+    let (core_a, core_b) = (a.core(), b.core());
+    let radius = a.radius() + b.radius();
+    if (vb - va).norm() < 1e-6 {
+        return None;
+    }
+    let difference = minkowski_difference(&core_a, &core_b);
+    let (time, normal) = sweep_point(&difference, radius, Point::zero(), va - vb)?;
+    return Some((time, normal * -1.0));
+}
+
+// Same as `rounded_collision`, keeping only the normals the edge allows
+fn edge_collision(edge: &Edge, ve: Vector, other: &Shape, vo: Vector) -> Option<(f64, Vector)> {
+    let (time, normal) = rounded_collision(&Shape::from(*edge), ve, other, vo)?;
+    let centroid = other.centroid() + (vo - ve) * time;
+    let normal = edge.contact_normal(normal, centroid)?;
+    return Some((time, normal));
+}
+
+fn project(polygon: &Polygon, axis: Vector) -> (f64, f64) {
+    let min = polygon
+        .vertices
+        .iter()
+        .map(|v| *v * axis)
+        .fold(f64::INFINITY, f64::min);
+    let max = polygon
+        .vertices
+        .iter()
+        .map(|v| *v * axis)
+        .fold(f64::NEG_INFINITY, f64::max);
+    return (min, max);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::square;
+    use crate::Capsule;
+    use crate::Circle;
+    use crate::Segment;
+    use similar::assert_similar;
+    use similar::Similar;
+
+    #[test]
+    fn collision_falling() {
+        let floor = square(0.0, -10.0, 10.0);
+        let falling = square(2.0, 4.0, 2.0);
+        let (time, normal) =
+            get_collision_time_and_normal(&floor, Vector::zero(), &falling, Vector::new(0.0, -2.0))
+                .unwrap();
+        assert_similar!(time, 2.0);
+        assert_similar!(normal, Vector::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn collision_normal_points_towards_b() {
+        let floor = square(0.0, -10.0, 10.0);
+        let falling = square(2.0, 4.0, 2.0);
+        let (time, normal) =
+            get_collision_time_and_normal(&falling, Vector::new(0.0, -2.0), &floor, Vector::zero())
+                .unwrap();
+        assert_similar!(time, 2.0);
+        assert_similar!(normal, Vector::new(0.0, -1.0));
+    }
+
+    #[test]
+    fn collision_diagonal() {
+        let p = square(0.0, 0.0, 1.0);
+        let q = square(3.0, 2.0, 1.0);
+        let (time, normal) =
+            get_collision_time_and_normal(&p, Vector::new(1.0, 1.0), &q, Vector::zero()).unwrap();
+        // The x axis separation is the last one to close
+        assert_similar!(time, 2.0);
+        assert_similar!(normal, Vector::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn collision_parallel_edges() {
+        let p = square(0.0, 0.0, 1.0);
+        let q = square(0.0, 3.0, 1.0);
+        let (time, normal) =
+            get_collision_time_and_normal(&p, Vector::new(0.0, 1.0), &q, Vector::new(0.0, -1.0))
+                .unwrap();
+        assert_similar!(time, 1.0);
+        assert_similar!(normal, Vector::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn collision_already_overlapping() {
+        let p = square(0.0, 0.0, 4.0);
+        let q = square(1.0, 3.0, 2.0);
+        let (time, normal) =
+            get_collision_time_and_normal(&p, Vector::zero(), &q, Vector::new(1.0, 0.0)).unwrap();
+        assert_similar!(time, 0.0);
+        assert_similar!(normal, Vector::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn collision_none_zero_relative_velocity() {
+        let p = square(0.0, 0.0, 1.0);
+        let q = square(3.0, 0.0, 1.0);
+        let velocity = Vector::new(1.0, 1.0);
+        assert_eq!(
+            get_collision_time_and_normal(&p, velocity, &q, velocity),
+            None
+        );
+    }
+
+    #[test]
+    fn collision_none_moving_apart() {
+        let p = square(0.0, 0.0, 1.0);
+        let q = square(3.0, 0.0, 1.0);
+        assert_eq!(
+            get_collision_time_and_normal(&p, Vector::new(-1.0, 0.0), &q, Vector::zero()),
+            None
+        );
+    }
+
+    #[test]
+    fn collision_none_missing() {
+        let p = square(0.0, 0.0, 1.0);
+        let q = square(3.0, 3.0, 1.0);
+        assert_eq!(
+            get_collision_time_and_normal(&p, Vector::new(1.0, 0.0), &q, Vector::zero()),
+            None
+        );
+    }
+
+    #[test]
+    fn circles_head_on() {
+        let a = Circle::new(Point::new(0.0, 0.0), 1.0);
+        let b = Circle::new(Point::new(5.0, 0.0), 1.0);
+        let (time, normal) = get_shapes_collision_time_and_normal(
+            &Shape::from(a),
+            Vector::new(1.0, 0.0),
+            &Shape::from(b),
+            Vector::new(-2.0, 0.0),
+        )
+        .unwrap();
+        assert_similar!(time, 1.0);
+        assert_similar!(normal, Vector::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn circles_missing() {
+        let a = Circle::new(Point::new(0.0, 0.0), 1.0);
+        let b = Circle::new(Point::new(5.0, 2.5), 1.0);
+        assert_eq!(
+            get_shapes_collision_time_and_normal(
+                &Shape::from(a),
+                Vector::new(1.0, 0.0),
+                &Shape::from(b),
+                Vector::zero(),
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn ball_falling_on_floor() {
+        let floor = Shape::from(square(0.0, -10.0, 10.0));
+        let ball = Shape::from(Circle::new(Point::new(5.0, 3.0), 1.0));
+        let (time, normal) = get_shapes_collision_time_and_normal(
+            &floor,
+            Vector::zero(),
+            &ball,
+            Vector::new(0.0, -2.0),
+        )
+        .unwrap();
+        assert_similar!(time, 1.0);
+        assert_similar!(normal, Vector::new(0.0, 1.0));
+
+        let (time, normal) = get_shapes_collision_time_and_normal(
+            &ball,
+            Vector::new(0.0, -2.0),
+            &floor,
+            Vector::zero(),
+        )
+        .unwrap();
+        assert_similar!(time, 1.0);
+        assert_similar!(normal, Vector::new(0.0, -1.0));
+    }
+
+    #[test]
+    fn ball_hits_corner() {
+        let block = Shape::from(square(0.0, 0.0, 1.0));
+        let ball = Shape::from(Circle::new(Point::new(1.6, 4.0), 1.0));
+        let (time, normal) = get_shapes_collision_time_and_normal(
+            &block,
+            Vector::zero(),
+            &ball,
+            Vector::new(0.0, -1.0),
+        )
+        .unwrap();
+        // Touches the corner (1, 1) when the center is at (1.6, 1.8)
+        assert_similar!(time, 2.2);
+        assert_similar!(normal, Vector::new(0.6, 0.8));
+    }
+
+    #[test]
+    fn ball_already_overlapping() {
+        let block = Shape::from(square(0.0, 0.0, 1.0));
+        let ball = Shape::from(Circle::new(Point::new(0.5, 1.2), 0.5));
+        let (time, normal) = get_shapes_collision_time_and_normal(
+            &block,
+            Vector::zero(),
+            &ball,
+            Vector::new(1.0, 0.0),
+        )
+        .unwrap();
+        assert_similar!(time, 0.0);
+        assert_similar!(normal, Vector::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn capsule_slides_onto_tiles() {
+        let tile = Shape::from(square(1.0, -1.0, 1.0));
+        let player = Shape::from(Capsule::new(
+            Segment::from_points(Point::new(-2.0, 0.5), Point::new(-1.0, 0.5)),
+            0.5,
+        ));
+        // Sliding at the height of the top of the tile, it only grazes its
+        // corner from above instead of catching on its side
+        let (time, normal) = get_shapes_collision_time_and_normal(
+            &tile,
+            Vector::zero(),
+            &player,
+            Vector::new(1.0, 0.0),
+        )
+        .unwrap();
+        assert_similar!(time, 2.0);
+        assert_similar!(normal, Vector::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn capsule_falls_on_corner() {
+        let block = Shape::from(square(0.0, 0.0, 1.0));
+        let capsule = Shape::from(Capsule::new(
+            Segment::from_points(Point::new(1.6, 3.0), Point::new(1.6, 4.0)),
+            1.0,
+        ));
+        let (time, normal) = get_shapes_collision_time_and_normal(
+            &block,
+            Vector::zero(),
+            &capsule,
+            Vector::new(0.0, -1.0),
+        )
+        .unwrap();
+        assert_similar!(time, 1.2);
+        assert_similar!(normal, Vector::new(0.6, 0.8));
+    }
+
+    #[test]
+    fn capsules_cross() {
+        let a = Shape::from(Capsule::new(
+            Segment::from_points(Point::new(0.0, -1.0), Point::new(0.0, 1.0)),
+            0.25,
+        ));
+        let b = Shape::from(Capsule::new(
+            Segment::from_points(Point::new(2.0, -1.0), Point::new(2.0, 1.0)),
+            0.25,
+        ));
+        let (time, normal) = get_shapes_collision_time_and_normal(
+            &a,
+            Vector::new(1.0, 0.0),
+            &b,
+            Vector::new(-0.5, 0.0),
+        )
+        .unwrap();
+        assert_similar!(time, 1.0);
+        assert_similar!(normal, Vector::new(1.0, 0.0));
+    }
+}
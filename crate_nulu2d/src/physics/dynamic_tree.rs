@@ -0,0 +1,394 @@
+use crate::Aabb;
+use std::collections::HashMap;
+
+const NULL: usize = usize::MAX;
+
+struct Node {
+    aabb: Aabb,
+    parent: usize,
+    child1: usize,
+    child2: usize,
+    // Leaves are at height 0
+    height: u32,
+    id: u32,
+}
+
+impl Node {
+    fn is_leaf(&self) -> bool {
+        return self.child1 == NULL;
+    }
+}
+
+/// Bounding volume hierarchy of fattened AABBs, one leaf per id.
+/// Leaves are only reinserted when the box they were given no longer fits in
+/// their fattened box, so slowly moving objects rarely touch the tree.
+/// Insertion follows Box2D's surface area heuristic and rotations keep the
+/// tree balanced.
+pub struct DynamicTree {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    root: usize,
+    leaves: HashMap<u32, usize>,
+    pub margin: f64,
+}
+
+impl DynamicTree {
+    pub fn new(margin: f64) -> DynamicTree {
+        DynamicTree {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: NULL,
+            leaves: HashMap::new(),
+            margin,
+        }
+    }
+
+    // Accessors
+    pub fn len(&self) -> usize {
+        return self.leaves.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.leaves.is_empty();
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        return self.leaves.contains_key(&id);
+    }
+
+    pub fn ids(&self) -> Vec<u32> {
+        let mut ids: Vec<u32> = self.leaves.keys().cloned().collect();
+        ids.sort();
+        return ids;
+    }
+
+    // The fattened box stored for `id`
+    pub fn fat_aabb(&self, id: u32) -> Option<Aabb> {
+        return self.leaves.get(&id).map(|leaf| self.nodes[*leaf].aabb);
+    }
+
+    // Number of levels below the root, 0 for a single leaf
+    pub fn height(&self) -> u32 {
+        if self.root == NULL {
+            return 0;
+        }
+        return self.nodes[self.root].height;
+    }
+
+    // Ids whose fattened boxes overlap `aabb`, sorted
+    pub fn query(&self, aabb: &Aabb) -> Vec<u32> {
+        let mut ids = Vec::new();
+        let mut stack = vec![self.root];
+        while let Some(index) = stack.pop() {
+            if index == NULL || !self.nodes[index].aabb.overlaps(aabb) {
+                continue;
+            }
+            let node = &self.nodes[index];
+            if node.is_leaf() {
+                ids.push(node.id);
+            } else {
+                stack.push(node.child1);
+                stack.push(node.child2);
+            }
+        }
+        ids.sort();
+        return ids;
+    }
+
+    // Pairs of ids whose fattened boxes overlap, with the smallest id first,
+    // sorted
+    pub fn pairs(&self) -> Vec<(u32, u32)> {
+        let mut pairs = Vec::new();
+        for (id, leaf) in self.leaves.iter() {
+            for other in self.query(&self.nodes[*leaf].aabb) {
+                if *id < other {
+                    pairs.push((*id, other));
+                }
+            }
+        }
+        pairs.sort();
+        return pairs;
+    }
+
+    // Mutators
+    // Replaces the leaf of `id` if there's already one
+    pub fn insert(&mut self, id: u32, aabb: Aabb) {
+        self.remove(id);
+        let leaf = self.allocate(Node {
+            aabb: aabb.expanded(self.margin),
+            parent: NULL,
+            child1: NULL,
+            child2: NULL,
+            height: 0,
+            id,
+        });
+        self.leaves.insert(id, leaf);
+        self.insert_leaf(leaf);
+    }
+
+    // Returns whether there was a leaf for `id`
+    pub fn remove(&mut self, id: u32) -> bool {
+        match self.leaves.remove(&id) {
+            Some(leaf) => {
+                self.remove_leaf(leaf);
+                self.free.push(leaf);
+                return true;
+            }
+            None => return false,
+        }
+    }
+
+    // Moves the leaf of `id` (inserting it if needed) when `aabb` isn't
+    // covered by its fattened box anymore, or when the fattened box got much
+    // bigger than needed. Returns whether the tree changed.
+    pub fn update(&mut self, id: u32, aabb: Aabb) -> bool {
+        if let Some(fat) = self.fat_aabb(id) {
+            let loose = aabb.expanded(4.0 * self.margin);
+            if fat.contains(&aabb) && loose.contains(&fat) {
+                return false;
+            }
+        }
+        self.insert(id, aabb);
+        return true;
+    }
+
+    fn allocate(&mut self, node: Node) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                return index;
+            }
+            None => {
+                self.nodes.push(node);
+                return self.nodes.len() - 1;
+            }
+        }
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        if self.root == NULL {
+            self.root = leaf;
+            return;
+        }
+
+        // Find the sibling whose union with the leaf adds the least perimeter
+        // to the tree
+        let leaf_aabb = self.nodes[leaf].aabb;
+        let mut index = self.root;
+        while !self.nodes[index].is_leaf() {
+            let node = &self.nodes[index];
+            let perimeter = node.aabb.perimeter();
+            let combined = node.aabb.union(&leaf_aabb).perimeter();
+            // Cost of making a new parent for this node and the leaf
+            let cost = 2.0 * combined;
+            // Minimum cost of pushing the leaf further down
+            let inheritance = 2.0 * (combined - perimeter);
+
+            let child_cost = |child: usize| {
+                let child = &self.nodes[child];
+                let union = child.aabb.union(&leaf_aabb).perimeter();
+                if child.is_leaf() {
+                    return union + inheritance;
+                }
+                return union - child.aabb.perimeter() + inheritance;
+            };
+            let (cost1, cost2) = (child_cost(node.child1), child_cost(node.child2));
+
+            if cost < cost1 && cost < cost2 {
+                break;
+            }
+            index = if cost1 < cost2 {
+                node.child1
+            } else {
+                node.child2
+            };
+        }
+
+        // New parent for the sibling and the leaf
+        let sibling = index;
+        let old_parent = self.nodes[sibling].parent;
+        let new_parent = self.allocate(Node {
+            aabb: leaf_aabb.union(&self.nodes[sibling].aabb),
+            parent: old_parent,
+            child1: sibling,
+            child2: leaf,
+            height: self.nodes[sibling].height + 1,
+            id: 0,
+        });
+        self.nodes[sibling].parent = new_parent;
+        self.nodes[leaf].parent = new_parent;
+        if old_parent == NULL {
+            self.root = new_parent;
+        } else if self.nodes[old_parent].child1 == sibling {
+            self.nodes[old_parent].child1 = new_parent;
+        } else {
+            self.nodes[old_parent].child2 = new_parent;
+        }
+
+        self.refit(self.nodes[leaf].parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        if leaf == self.root {
+            self.root = NULL;
+            return;
+        }
+
+        // The sibling takes the place of the parent
+        let parent = self.nodes[leaf].parent;
+        let grand_parent = self.nodes[parent].parent;
+        let sibling = if self.nodes[parent].child1 == leaf {
+            self.nodes[parent].child2
+        } else {
+            self.nodes[parent].child1
+        };
+        self.free.push(parent);
+        self.nodes[sibling].parent = grand_parent;
+        if grand_parent == NULL {
+            self.root = sibling;
+            return;
+        }
+        if self.nodes[grand_parent].child1 == parent {
+            self.nodes[grand_parent].child1 = sibling;
+        } else {
+            self.nodes[grand_parent].child2 = sibling;
+        }
+        self.refit(grand_parent);
+    }
+
+    // Walks up from `index`, rebalancing and recomputing boxes and heights
+    fn refit(&mut self, index: usize) {
+        let mut index = index;
+        while index != NULL {
+            index = self.balance(index);
+            let (child1, child2) = (self.nodes[index].child1, self.nodes[index].child2);
+            self.nodes[index].height =
+                1 + u32::max(self.nodes[child1].height, self.nodes[child2].height);
+            self.nodes[index].aabb = self.nodes[child1].aabb.union(&self.nodes[child2].aabb);
+            index = self.nodes[index].parent;
+        }
+    }
+
+    // If one child of `a` is more than one level taller than the other, it is
+    // rotated up to take the place of `a`. Returns the node now at that place.
+    fn balance(&mut self, a: usize) -> usize {
+        if self.nodes[a].is_leaf() || self.nodes[a].height < 2 {
+            return a;
+        }
+        let (b, c) = (self.nodes[a].child1, self.nodes[a].child2);
+        let balance = self.nodes[c].height as i64 - self.nodes[b].height as i64;
+        if balance > 1 {
+            self.rotate_up(a, c, b);
+            return c;
+        }
+        if balance < -1 {
+            self.rotate_up(a, b, c);
+            return b;
+        }
+        return a;
+    }
+
+    // `up` is the tall child of `a`, `other` its sibling. `up` takes the place
+    // of `a`, and `a` takes the place of the shortest child of `up`.
+    fn rotate_up(&mut self, a: usize, up: usize, other: usize) {
+        let (f, g) = (self.nodes[up].child1, self.nodes[up].child2);
+
+        let parent = self.nodes[a].parent;
+        self.nodes[up].parent = parent;
+        self.nodes[a].parent = up;
+        if parent == NULL {
+            self.root = up;
+        } else if self.nodes[parent].child1 == a {
+            self.nodes[parent].child1 = up;
+        } else {
+            self.nodes[parent].child2 = up;
+        }
+
+        // The tallest grandchild stays under `up`, the other moves under `a`
+        let (kept, moved) = if self.nodes[f].height > self.nodes[g].height {
+            (f, g)
+        } else {
+            (g, f)
+        };
+        self.nodes[up].child1 = a;
+        self.nodes[up].child2 = kept;
+        self.nodes[a].child1 = other;
+        self.nodes[a].child2 = moved;
+        self.nodes[moved].parent = a;
+
+        self.nodes[a].aabb = self.nodes[other].aabb.union(&self.nodes[moved].aabb);
+        self.nodes[a].height = 1 + u32::max(self.nodes[other].height, self.nodes[moved].height);
+        self.nodes[up].aabb = self.nodes[a].aabb.union(&self.nodes[kept].aabb);
+        self.nodes[up].height = 1 + u32::max(self.nodes[a].height, self.nodes[kept].height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+    use crate::Vector;
+
+    fn unit_at(x: f64, y: f64) -> Aabb {
+        Aabb::new(Point::new(x, y), Point::new(x + 1.0, y + 1.0))
+    }
+
+    #[test]
+    fn query_and_pairs() {
+        let mut tree = DynamicTree::new(0.0);
+        tree.insert(1, unit_at(0.0, 0.0));
+        tree.insert(2, unit_at(0.5, 0.5));
+        tree.insert(3, unit_at(5.0, 0.0));
+        tree.insert(4, unit_at(5.5, 0.0));
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.query(&unit_at(-0.5, -0.5)), vec![1, 2]);
+        assert_eq!(tree.query(&unit_at(10.0, 0.0)), Vec::<u32>::new());
+        assert_eq!(tree.pairs(), vec![(1, 2), (3, 4)]);
+    }
+
+    #[test]
+    fn remove() {
+        let mut tree = DynamicTree::new(0.0);
+        for id in 0..10 {
+            tree.insert(id, unit_at(id as f64 * 0.5, 0.0));
+        }
+        assert!(tree.remove(3));
+        assert!(!tree.remove(3));
+        assert!(!tree.contains(3));
+        assert_eq!(tree.query(&unit_at(1.5, 0.0)), vec![1, 2, 4, 5]);
+        for id in 0..10 {
+            tree.remove(id);
+        }
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), 0);
+        assert_eq!(tree.pairs(), vec![]);
+    }
+
+    #[test]
+    fn update_only_moves_leaves_out_of_their_fat_box() {
+        let mut tree = DynamicTree::new(0.5);
+        assert!(tree.update(1, unit_at(0.0, 0.0)));
+        assert!(!tree.update(1, unit_at(0.25, -0.25)));
+        assert_eq!(tree.fat_aabb(1), Some(unit_at(0.0, 0.0).expanded(0.5)));
+        assert!(tree.update(1, unit_at(1.0, 0.0)));
+        assert_eq!(tree.fat_aabb(1), Some(unit_at(1.0, 0.0).expanded(0.5)));
+
+        // Shrinks back once the swept box isn't needed anymore
+        let swept = unit_at(1.0, 0.0).swept(Vector::new(10.0, 0.0));
+        assert!(tree.update(1, swept));
+        assert!(tree.update(1, unit_at(1.0, 0.0)));
+        assert_eq!(tree.fat_aabb(1), Some(unit_at(1.0, 0.0).expanded(0.5)));
+    }
+
+    #[test]
+    fn stays_balanced() {
+        // Sorted insertions are the worst case for an unbalanced tree
+        let mut tree = DynamicTree::new(0.1);
+        for id in 0..1000 {
+            tree.insert(id, unit_at(id as f64 * 2.0, 0.0));
+        }
+        assert!(tree.height() <= 20, "height {}", tree.height());
+        assert_eq!(tree.pairs(), vec![]);
+        assert_eq!(tree.query(&unit_at(1000.0, 0.0)), vec![500]);
+    }
+}
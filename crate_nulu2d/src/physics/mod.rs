@@ -1,3 +1,16 @@
 pub mod body;
+pub mod broadphase;
 pub mod collision;
+pub mod dynamic_tree;
 pub mod world;
+
+pub use self::body::Body;
+pub use self::broadphase::BroadPhase;
+pub use self::broadphase::BruteForce;
+pub use self::broadphase::SpatialGrid;
+pub use self::broadphase::SweepAndPrune;
+pub use self::dynamic_tree::DynamicTree;
+pub use self::world::Separation;
+pub use self::world::ShapeCastHit;
+pub use self::world::StepStats;
+pub use self::world::World;
@@ -1,51 +1,186 @@
 use crate::physics::body::Body;
+use crate::physics::broadphase::BroadPhase;
 use crate::physics::collision;
+use crate::physics::dynamic_tree::DynamicTree;
+use crate::Aabb;
+use crate::Chain;
+use crate::Point;
 use crate::Polygon;
+use crate::Ray;
+use crate::RayHit;
+use crate::Segment;
+use crate::Shape;
+use crate::Transform;
+use crate::Vector;
 use std::collections::HashMap;
 
 const COLLISION_LOOP_TRIES: u32 = 100;
+const RESPONSE_ITERATIONS: u32 = 20;
+// Slack for the bounding boxes of bodies that are about to touch
+const BOUNDS_MARGIN: f64 = 1e-6;
+// How much the boxes in the broadphase tree are fattened
+const TREE_MARGIN: f64 = 0.1;
+
+/// Strategy used to push overlapping bodies apart before and after each step.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Separation {
+    /// Overlapping bodies are left as they are.
+    Off,
+    /// Every overlapping pair is pushed apart once.
+    SinglePass,
+    /// Pairs are pushed apart until no overlaps remain, or `max_passes` is reached.
+    Iterative { max_passes: u32 },
+}
+
+/// First body hit by a shape cast: `time` is the fraction of the motion
+/// done before touching it, and `normal` points from the body towards the
+/// cast shape.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShapeCastHit {
+    pub id: u32,
+    pub time: f64,
+    pub normal: Vector,
+}
+
+/// Counters for the work done since the start of the last `update`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct StepStats {
+    /// Iterations of the collision loop
+    pub sub_steps: u32,
+    /// Pairs whose boxes overlap in the broadphase
+    pub candidate_pairs: usize,
+    /// Pairs that went through the narrowphase
+    pub pairs_tested: usize,
+    /// Collisions that were responded to
+    pub collisions: usize,
+}
 
 pub struct World {
     pub bodies: HashMap<u32, Body>,
     pub current_id: u32,
+    pub separation: Separation,
+    pub stats: StepStats,
+    pub broadphase: Box<dyn BroadPhase>,
 }
 
 impl World {
     pub fn new() -> World {
+        World::with_broadphase(DynamicTree::new(TREE_MARGIN))
+    }
+
+    pub fn with_broadphase(broadphase: impl BroadPhase + 'static) -> World {
         World {
             bodies: HashMap::new(),
             current_id: 0,
+            separation: Separation::Iterative { max_passes: 10 },
+            stats: StepStats::default(),
+            broadphase: Box::new(broadphase),
         }
     }
 
     pub fn add_body(
         &mut self,
-        shape: Polygon,
+        shape: impl Into<Shape>,
         mass: f64,
         friction: f64,
         frictionless: bool,
         gravityless: bool,
     ) -> u32 {
+        self.insert_body(Body::new(shape, mass, friction, frictionless, gravityless))
+    }
+
+    // The mass is computed from the area of the shape
+    pub fn add_body_with_density(
+        &mut self,
+        shape: impl Into<Shape>,
+        density: f64,
+        friction: f64,
+        frictionless: bool,
+        gravityless: bool,
+    ) -> u32 {
+        self.insert_body(Body::from_density(
+            shape,
+            density,
+            friction,
+            frictionless,
+            gravityless,
+        ))
+    }
+
+    fn insert_body(&mut self, body: Body) -> u32 {
         self.current_id += 1;
-        self.bodies.insert(
-            self.current_id,
-            Body::new(shape, mass, friction, frictionless, gravityless),
-        );
+        let aabb = body.aabb().expanded(BOUNDS_MARGIN);
+        self.broadphase.update(self.current_id, aabb);
+        self.bodies.insert(self.current_id, body);
         self.current_id
     }
 
-    pub fn add_static_body(&mut self, shape: Polygon, friction: f64) -> u32 {
+    pub fn add_static_body(&mut self, shape: impl Into<Shape>, friction: f64) -> u32 {
         self.add_body(shape, f64::INFINITY, friction, false, true)
     }
 
+    // Concave shapes are split into convex pieces, one static body each
+    pub fn add_concave_static_body(&mut self, shape: &Polygon, friction: f64) -> Vec<u32> {
+        return shape
+            .convex_decomposition()
+            .into_iter()
+            .map(|piece| self.add_static_body(piece, friction))
+            .collect();
+    }
+
+    // Terrain outlines get one static body per edge
+    pub fn add_chain(&mut self, chain: &Chain, friction: f64) -> Vec<u32> {
+        return chain
+            .edges()
+            .into_iter()
+            .map(|edge| self.add_static_body(edge, friction))
+            .collect();
+    }
+
     pub fn body(&mut self, id: u32) -> Option<&mut Body> {
         self.bodies.get_mut(&id)
     }
 
+    /// Replaces a polygon body with the two pieces on each side of the line
+    /// through `line`, and returns their ids (left piece first). The mass is
+    /// shared according to the area of the pieces, and each piece moves like
+    /// the part of the body it was cut from. Returns `None`, leaving the body
+    /// untouched, if it isn't a polygon or the line doesn't cut through it.
+    pub fn slice_body(&mut self, id: u32, line: Segment) -> Option<(u32, u32)> {
+        // Cut in local space, so the pieces keep the angle of the body
+        let body = self.bodies.get(&id)?;
+        let local_line = Segment::from_points(body.to_local(line.a), body.to_local(line.b));
+        let (left, right) = match body.local_shape() {
+            Shape::Polygon(polygon) => polygon.split_by_line(local_line)?,
+            _ => return None,
+        };
+
+        let body = self.bodies.remove(&id).unwrap();
+        self.broadphase.remove(id);
+        let density = body.mass / body.shape().area();
+        let mut piece_ids = Vec::new();
+        for piece in [left, right] {
+            let mut piece = Body::from_density(
+                piece,
+                density,
+                body.friction,
+                body.frictionless,
+                body.gravityless,
+            );
+            let center = body.to_world(piece.center());
+            piece.set_transform(Transform::new(center, body.angle()));
+            piece.velocity = body.velocity_at(center);
+            piece.angular_velocity = body.angular_velocity;
+            piece_ids.push(self.insert_body(piece));
+        }
+        return Some((piece_ids[0], piece_ids[1]));
+    }
+
     pub fn update(&mut self, delta: f64) {
-        // separate_bodies()
+        self.stats = StepStats::default();
+        self.separate_bodies();
 
-        for (_, body) in &mut self.bodies {
+        for body in self.bodies.values_mut() {
             // Gravity
             if !body.gravityless {
                 body.velocity.y -= 4.0;
@@ -56,141 +191,1240 @@ impl World {
         let mut tries = COLLISION_LOOP_TRIES;
         let mut time_left = delta;
 
-        while time_left >= 1e-3 && tries >= 0 {
-            // Find earliest collision
-            let mut earliest_collision_time = None;
-            let mut earliest_collision_normal = None;
-            let mut earliest_collision_body_a_id = None;
-            let mut earliest_collision_body_b_id = None;
-
-            for (id_a, a) in &self.bodies {
-                for (id_b, b) in &self.bodies {
-                    if id_a == id_b {
-                        continue;
-                    }
-                    if let Some((collision_time, collision_normal)) =
-                        collision::get_collision_time_and_normal(
-                            &a.shape, a.velocity, &b.shape, b.velocity,
-                        )
-                    {
-                        if collision_time <= time_left {
-                            if earliest_collision_time.is_none()
-                                || collision_time <= earliest_collision_time.unwrap()
-                            {
-                                earliest_collision_time = Some(collision_time);
-                                earliest_collision_normal = Some(collision_normal);
-                                earliest_collision_body_a_id = Some(*id_a);
-                                earliest_collision_body_b_id = Some(*id_b);
-                            }
-                        }
+        while time_left >= 1e-3 && tries > 0 {
+            let earliest_collision = self.earliest_collision(time_left);
+
+            // Goto earliest collision
+            let elapsed_time = match earliest_collision {
+                Some((collision_time, _, _, _)) if collision_time <= 1e-10 => 0.0,
+                Some((collision_time, _, _, _)) => collision_time - 1e-10,
+                None => time_left,
+            };
+            self.integrate(elapsed_time);
+
+            // Collision response
+            if let Some((_, normal, id_a, id_b)) = earliest_collision {
+                self.respond(id_a, id_b, normal);
+                self.stats.collisions += 1;
+            }
+
+            // Advance cycle
+            self.stats.sub_steps += 1;
+            time_left -= elapsed_time;
+            tries -= 1;
+        }
+
+        // If there's still time left, we ignore collisions and just attempt to finish the update
+        self.integrate(time_left);
+        self.separate_bodies();
+        // Ready for queries until the next update
+        self.update_broadphase(0.0);
+    }
+
+    /// Returns the first body hit by the ray within `max_distance`, among the
+    /// ones whose id passes `filter`. Bodies containing the origin of the ray
+    /// aren't hit.
+    pub fn raycast(
+        &self,
+        ray: &Ray,
+        max_distance: f64,
+        filter: impl Fn(u32) -> bool,
+    ) -> Option<(u32, RayHit)> {
+        let mut first_hit: Option<(u32, RayHit)> = None;
+        for id in self.candidates(&ray_bounds(ray, max_distance), filter) {
+            if let Some(hit) = self.bodies[&id].shape().raycast(ray, max_distance) {
+                // Candidates are sorted, so the lowest id wins ties
+                let closer = match first_hit {
+                    Some((_, first)) => hit.distance < first.distance,
+                    None => true,
+                };
+                if closer {
+                    first_hit = Some((id, hit));
+                }
+            }
+        }
+        return first_hit;
+    }
+
+    /// Returns any body hit by the ray, which is cheaper than finding the
+    /// first one (e.g. for line of sight checks).
+    pub fn raycast_any(
+        &self,
+        ray: &Ray,
+        max_distance: f64,
+        filter: impl Fn(u32) -> bool,
+    ) -> Option<(u32, RayHit)> {
+        return self
+            .candidates(&ray_bounds(ray, max_distance), filter)
+            .into_iter()
+            .find_map(|id| {
+                let hit = self.bodies[&id].shape().raycast(ray, max_distance)?;
+                Some((id, hit))
+            });
+    }
+
+    /// Returns every body hit by the ray, closest first.
+    pub fn raycast_all(
+        &self,
+        ray: &Ray,
+        max_distance: f64,
+        filter: impl Fn(u32) -> bool,
+    ) -> Vec<(u32, RayHit)> {
+        let mut hits: Vec<(u32, RayHit)> = self
+            .candidates(&ray_bounds(ray, max_distance), filter)
+            .into_iter()
+            .filter_map(|id| {
+                let hit = self.bodies[&id].shape().raycast(ray, max_distance)?;
+                Some((id, hit))
+            })
+            .collect();
+        // Stable, so the lowest id wins ties
+        hits.sort_by(|(_, a), (_, b)| a.distance.partial_cmp(&b.distance).unwrap());
+        return hits;
+    }
+
+    /// Returns the first body `shape` would hit if it moved by `motion`, the
+    /// other bodies staying where they are, among the ones whose id passes
+    /// `filter`. Bodies the motion leads away from are ignored.
+    pub fn shape_cast(
+        &self,
+        shape: &Polygon,
+        motion: Vector,
+        filter: impl Fn(u32) -> bool,
+    ) -> Option<ShapeCastHit> {
+        let shape = Shape::from(shape.clone());
+        let bounds = shape.aabb().swept(motion).expanded(BOUNDS_MARGIN);
+        let mut first_hit: Option<ShapeCastHit> = None;
+        for id in self.candidates(&bounds, filter) {
+            let body = &self.bodies[&id];
+            if let Some((time, normal)) = collision::get_shapes_collision_time_and_normal(
+                &shape,
+                motion,
+                body.shape(),
+                Vector::zero(),
+            ) {
+                let approaching = motion * normal > 0.0;
+                let earlier = match first_hit {
+                    Some(hit) => time < hit.time,
+                    None => true,
+                };
+                if approaching && time <= 1.0 && earlier {
+                    first_hit = Some(ShapeCastHit {
+                        id,
+                        time,
+                        normal: normal * -1.0,
+                    });
+                }
+            }
+        }
+        return first_hit;
+    }
+
+    // Ids of the bodies whose boxes overlap `bounds` and pass `filter`, sorted.
+    // The broadphase is kept current by `update` and when adding bodies, so
+    // bodies moved by hand since then are found where they were.
+    fn candidates(&self, bounds: &Aabb, filter: impl Fn(u32) -> bool) -> Vec<u32> {
+        return self
+            .broadphase
+            .query(bounds)
+            .into_iter()
+            .filter(|id| self.bodies.contains_key(id) && filter(*id))
+            .collect();
+    }
+
+    /// Pushes overlapping bodies apart along their minimum translation vector,
+    /// following the `separation` strategy. Each body of the pair is moved
+    /// inversely to its share of the total mass, so static bodies never move.
+    /// Returns whether no overlaps are left afterwards.
+    pub fn separate_bodies(&mut self) -> bool {
+        match self.separation {
+            Separation::Off => {}
+            Separation::SinglePass => {
+                self.separation_pass();
+            }
+            Separation::Iterative { max_passes } => {
+                for _ in 0..max_passes {
+                    if !self.separation_pass() {
+                        return true;
                     }
                 }
             }
+        }
+        return !self.any_overlap();
+    }
 
-            // Goto earliest collision
-            let mut elapsed_time;
-            if let Some(earliest_collision_time) = earliest_collision_time {
-                if earliest_collision_time <= 1e-10 {
-                    elapsed_time = 0.0;
-                } else {
-                    elapsed_time = earliest_collision_time - 1e-10;
+    // Gives the broadphase the boxes the bodies will sweep within `time`
+    fn update_broadphase(&mut self, time: f64) {
+        for id in self.broadphase.ids() {
+            if !self.bodies.contains_key(&id) {
+                self.broadphase.remove(id);
+            }
+        }
+        for (id, body) in self.bodies.iter() {
+            let swept = body.aabb().swept(body.velocity * time);
+            self.broadphase.update(*id, swept.expanded(BOUNDS_MARGIN));
+        }
+    }
+
+    // Pairs of bodies that may touch within `time`, sorted, according to
+    // the broadphase. Pairs of static bodies are left out.
+    fn candidate_pairs(&mut self, time: f64) -> Vec<(u32, u32)> {
+        self.update_broadphase(time);
+        let pairs = self.dynamic_pairs();
+        self.stats.candidate_pairs += pairs.len();
+        return pairs;
+    }
+
+    // Pairs from the broadphase, without the static ones which never move
+    fn dynamic_pairs(&self) -> Vec<(u32, u32)> {
+        let bodies = &self.bodies;
+        return self
+            .broadphase
+            .pairs()
+            .into_iter()
+            .filter(|(a, b)| bodies[a].mass != f64::INFINITY || bodies[b].mass != f64::INFINITY)
+            .collect();
+    }
+
+    fn any_overlap(&mut self) -> bool {
+        self.update_broadphase(0.0);
+        return self.dynamic_pairs().into_iter().any(|(id_a, id_b)| {
+            let (a, b) = (&self.bodies[&id_a], &self.bodies[&id_b]);
+            a.aabb().overlaps(&b.aabb()) && a.shape().overlaps(b.shape())
+        });
+    }
+
+    // Returns the time, normal and ids of the first pair of bodies that will
+    // collide within `time_left`. Pairs that are already separating are skipped.
+    fn earliest_collision(&mut self, time_left: f64) -> Option<(f64, Vector, u32, u32)> {
+        let mut earliest_collision: Option<(f64, Vector, u32, u32)> = None;
+
+        for (id_a, id_b) in self.candidate_pairs(time_left) {
+            let a = &self.bodies[&id_a];
+            let b = &self.bodies[&id_b];
+            // Bodies whose paths never cross can't collide
+            let swept_a = a.aabb().swept(a.velocity * time_left);
+            let swept_b = b.aabb().swept(b.velocity * time_left);
+            if !swept_a.expanded(BOUNDS_MARGIN).overlaps(&swept_b) {
+                continue;
+            }
+            self.stats.pairs_tested += 1;
+            if let Some((collision_time, collision_normal)) =
+                collision::get_shapes_collision_time_and_normal(
+                    a.shape(),
+                    a.velocity,
+                    b.shape(),
+                    b.velocity,
+                )
+            {
+                let approaching = (a.velocity - b.velocity) * collision_normal > 0.0;
+                let earlier = match earliest_collision {
+                    Some((earliest_time, _, _, _)) => collision_time <= earliest_time,
+                    None => true,
+                };
+                if approaching && collision_time <= time_left && earlier {
+                    earliest_collision = Some((collision_time, collision_normal, id_a, id_b));
                 }
-            } else {
-                elapsed_time = time_left;
             }
+        }
+        return earliest_collision;
+    }
 
-            for (_, body) in &mut self.bodies {
-                body.move_xy(body.velocity * elapsed_time);
+    fn integrate(&mut self, time: f64) {
+        for body in self.bodies.values_mut() {
+            body.move_xy(body.velocity * time);
+            if body.angular_velocity != 0.0 {
+                body.rotate(body.angular_velocity * time);
             }
+        }
+    }
+
+    // Bodies usually rest on several others at once (e.g. a row of tiles),
+    // so the colliding pair is solved together with every other contact of
+    // its bodies. Pushing them one pair at a time would make them spin.
+    fn respond(&mut self, id_a: u32, id_b: u32, normal: Vector) {
+        let mut pairs = vec![(id_a, id_b)];
+        for id in [id_a, id_b] {
+            if self.bodies[&id].mass == f64::INFINITY {
+                continue;
+            }
+            let bounds = self.bodies[&id].aabb().expanded(BOUNDS_MARGIN);
+            for other in self.broadphase.query(&bounds) {
+                let pair = (id.min(other), id.max(other));
+                if other != id && !pairs.contains(&pair) && !pairs.contains(&(pair.1, pair.0)) {
+                    pairs.push(pair);
+                }
+            }
+        }
+
+        let mut contacts: Vec<Contact> = Vec::new();
+        let mut touching = false;
+        for (i, (id_a, id_b)) in pairs.into_iter().enumerate() {
+            let (a, b) = (&self.bodies[&id_a], &self.bodies[&id_b]);
+            if let Some(manifold) = a.shape().contact_manifold(b.shape()) {
+                touching = touching || i == 0;
+                let points = manifold.points.iter().map(|p| (p.point, p.point));
+                contacts.push(Contact::new(id_a, id_b, manifold.normal, points.collect()));
+            }
+        }
+        let mut contacts = merge_static_contacts(contacts, &self.bodies);
+        // The swept test doesn't account for rotation, so if the colliding
+        // shapes aren't actually touching the impulses go through their
+        // centers of mass
+        if !touching {
+            let points = vec![(self.bodies[&id_a].center(), self.bodies[&id_b].center())];
+            contacts.push(Contact::new(id_a, id_b, normal, points));
+        }
+        for contact in contacts.iter_mut() {
+            prepare_contact(
+                contact,
+                &self.bodies[&contact.id_a],
+                &self.bodies[&contact.id_b],
+            );
+        }
+
+        // Friction and normal impulses affect each other through rotation, so
+        // they are solved a few times in turns. Each turn ends with every
+        // contact pushed, so that friction isn't applied to a body one of the
+        // contacts has set spinning, and none is left approaching.
+        // The bodies are taken out of the map for all the iterations, and
+        // the contacts refer to them by index
+        let mut ids: Vec<u32> = Vec::new();
+        for contact in contacts.iter() {
+            for id in [contact.id_a, contact.id_b] {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+        let index = |id: u32| ids.iter().position(|other| *other == id).unwrap();
+        let indices: Vec<(usize, usize)> = contacts
+            .iter()
+            .map(|c| (index(c.id_a), index(c.id_b)))
+            .collect();
+        let mut bodies: Vec<Body> = ids
+            .iter()
+            .map(|id| self.bodies.remove(id).unwrap())
+            .collect();
+        for _ in 0..RESPONSE_ITERATIONS {
+            solve_contacts(&mut contacts, &indices, &mut bodies, solve_friction);
+            solve_contacts(&mut contacts, &indices, &mut bodies, solve_normal);
+        }
+        for (id, body) in ids.into_iter().zip(bodies) {
+            self.bodies.insert(id, body);
+        }
+    }
+
+    // Returns whether any overlapping pair was found
+    fn separation_pass(&mut self) -> bool {
+        let mut overlapping = false;
+        for (id_a, id_b) in self.candidate_pairs(0.0) {
+            let a = &self.bodies[&id_a];
+            let b = &self.bodies[&id_b];
+            if !a.aabb().overlaps(&b.aabb()) {
+                continue;
+            }
+            if let Some(mtv) = a.shape().mtv(b.shape()) {
+                overlapping = true;
+                let a_mass_ratio = get_ratio(a.mass, b.mass);
+                self.bodies
+                    .get_mut(&id_b)
+                    .unwrap()
+                    .move_xy(mtv * a_mass_ratio);
+                self.bodies
+                    .get_mut(&id_a)
+                    .unwrap()
+                    .move_xy(mtv * -(1.0 - a_mass_ratio));
+            }
+        }
+        return overlapping;
+    }
+}
+
+impl Default for World {
+    fn default() -> World {
+        return World::new();
+    }
+}
+
+fn ray_bounds(ray: &Ray, max_distance: f64) -> Aabb {
+    return Aabb::from_points(&[ray.origin, ray.point_at(max_distance)]);
+}
+
+// Contact points of two bodies, with the impulses accumulated at each of
+// them along the normal, which points from the first body to the second
+struct Contact {
+    id_a: u32,
+    id_b: u32,
+    normal: Vector,
+    points: Vec<(Point, Point)>,
+    accumulated: Vec<f64>,
+    // Relative velocity along the contact plane that friction slows the
+    // bodies down to, or `None` if either body is frictionless
+    target_tangent_velocity: Option<f64>,
+}
+
+impl Contact {
+    fn new(id_a: u32, id_b: u32, normal: Vector, points: Vec<(Point, Point)>) -> Contact {
+        Contact {
+            id_a,
+            id_b,
+            normal,
+            points,
+            accumulated: Vec::new(),
+            target_tangent_velocity: None,
+        }
+    }
+
+    // The same contact, seen from the other body
+    fn flipped(self) -> Contact {
+        return Contact {
+            id_a: self.id_b,
+            id_b: self.id_a,
+            normal: self.normal * -1.0,
+            points: self.points.iter().map(|(pa, pb)| (*pb, *pa)).collect(),
+            ..self
+        };
+    }
+}
+
+// Contacts of a body with static bodies along the same line, like the ones
+// of a body resting on a row of tiles, are merged as if the static bodies
+// were a single one. Otherwise the points on each side of a seam are solved
+// against each other, which never quite settles and leaves the body spinning.
+fn merge_static_contacts(contacts: Vec<Contact>, bodies: &HashMap<u32, Body>) -> Vec<Contact> {
+    let is_static = |id: u32| bodies[&id].mass == f64::INFINITY;
+    let mut merged: Vec<Contact> = Vec::new();
+    for contact in contacts {
+        // Static body first
+        let contact = if is_static(contact.id_b) {
+            contact.flipped()
+        } else {
+            contact
+        };
+        let same_surface = merged.iter_mut().find(|other| {
+            is_static(contact.id_a)
+                && is_static(other.id_a)
+                && other.id_b == contact.id_b
+                && other.normal * contact.normal > 1.0 - 1e-9
+        });
+        match same_surface {
+            Some(other) => other.points.extend(contact.points),
+            None => merged.push(contact),
+        }
+    }
+    return merged;
+}
+
+// Keeps the two outermost contact points, or only their middle if they
+// can't be solved together, and sets the friction from the velocities the
+// bodies had before the collision
+fn prepare_contact(contact: &mut Contact, a: &Body, b: &Body) {
+    let normal = contact.normal;
+    if contact.points.len() > 2 {
+        let along = |p: &&(Point, Point)| p.0 * normal.perp();
+        let compare =
+            |p: &&(Point, Point), q: &&(Point, Point)| along(p).partial_cmp(&along(q)).unwrap();
+        let first = *contact.points.iter().min_by(compare).unwrap();
+        let last = *contact.points.iter().max_by(compare).unwrap();
+        contact.points = vec![first, last];
+    }
+    if contact.points.len() == 2 && !well_conditioned(a, b, normal, &contact.points) {
+        contact.points = vec![middle(&contact.points)];
+    }
+    contact.accumulated = vec![0.0; contact.points.len()];
+
+    // Velocity along plane (friction)
+    contact.target_tangent_velocity = if a.frictionless || b.frictionless {
+        None
+    } else {
+        let (pa, pb) = middle(&contact.points);
+        let tangent_velocity = (b.velocity_at(pb) - a.velocity_at(pa)) * normal.perp();
+        Some(tangent_velocity * (1.0 - a.friction) * (1.0 - b.friction))
+    };
+}
+
+fn solve_contacts(
+    contacts: &mut [Contact],
+    indices: &[(usize, usize)],
+    bodies: &mut [Body],
+    solve: fn(&mut Contact, &mut Body, &mut Body),
+) {
+    for (contact, (i, j)) in contacts.iter_mut().zip(indices) {
+        let (a, b) = pair_mut(bodies, *i, *j);
+        solve(contact, a, b);
+    }
+}
+
+// Two different bodies of the slice, both mutable
+fn pair_mut(bodies: &mut [Body], i: usize, j: usize) -> (&mut Body, &mut Body) {
+    if i < j {
+        let (left, right) = bodies.split_at_mut(j);
+        return (&mut left[i], &mut right[0]);
+    }
+    let (left, right) = bodies.split_at_mut(i);
+    return (&mut right[0], &mut left[j]);
+}
+
+// Stops two colliding bodies from approaching each other along the contact
+// normal. Impulses are applied at the contact points, so off-center contacts
+// make the bodies spin. They are accumulated, so that a later call can take
+// back part of an earlier push, and never add kinetic energy.
+fn solve_normal(contact: &mut Contact, a: &mut Body, b: &mut Body) {
+    let normal = contact.normal;
+    let impulses = normal_impulses(a, b, normal, &contact.points, &contact.accumulated);
+    for (i, (pa, pb)) in contact.points.iter().enumerate() {
+        let impulse = impulses[i] - contact.accumulated[i];
+        a.apply_impulse(normal * -impulse, *pa);
+        b.apply_impulse(normal * impulse, *pb);
+    }
+    contact.accumulated = impulses;
+}
+
+// Slows the bodies down along the contact plane, without ever speeding them
+// up or reversing their sliding
+fn solve_friction(contact: &mut Contact, a: &mut Body, b: &mut Body) {
+    let target = match contact.target_tangent_velocity {
+        Some(target) => target,
+        None => return,
+    };
+    let (pa, pb) = middle(&contact.points);
+    let tangent = contact.normal.perp();
+    let k = inverse_effective_mass(a, b, (pa, pb), (pa, pb), tangent);
+    if k > 0.0 {
+        let tangent_velocity = (b.velocity_at(pb) - a.velocity_at(pa)) * tangent;
+        let target = target.clamp(
+            f64::min(0.0, tangent_velocity),
+            f64::max(0.0, tangent_velocity),
+        );
+        let impulse = (target - tangent_velocity) / k;
+        a.apply_impulse(tangent * -impulse, pa);
+        b.apply_impulse(tangent * impulse, pb);
+    }
+}
+
+// Total non-negative impulses along the normal that leave no contact point
+// approaching, given the impulses already applied. Two contact points are
+// solved together (as in Box2D's block solver), so that a flat contact
+// doesn't make the bodies spin.
+fn normal_impulses(
+    a: &Body,
+    b: &Body,
+    normal: Vector,
+    contacts: &[(Point, Point)],
+    accumulated: &[f64],
+) -> Vec<f64> {
+    let normal_velocity =
+        |(pa, pb): (Point, Point)| (b.velocity_at(pb) - a.velocity_at(pa)) * normal;
+    let k = |i: usize, j: usize| inverse_effective_mass(a, b, contacts[i], contacts[j], normal);
+
+    if contacts.len() == 1 {
+        let k11 = k(0, 0);
+        if k11 <= 0.0 {
+            return vec![accumulated[0]];
+        }
+        let impulse = accumulated[0] - normal_velocity(contacts[0]) / k11;
+        return vec![f64::max(0.0, impulse)];
+    }
 
-            /*
-            // Collision Response
-            if let(earliest_collision_time) = earliest_collision_time {
-                if let(earliest_collision_normal) = earliest_collision_normal {
+    // Normal velocities the contact points would have without any impulse
+    let (k11, k22, k12) = (k(0, 0), k(1, 1), k(0, 1));
+    let (a1, a2) = (accumulated[0], accumulated[1]);
+    let vn1 = normal_velocity(contacts[0]) - k11 * a1 - k12 * a2;
+    let vn2 = normal_velocity(contacts[1]) - k12 * a1 - k22 * a2;
 
-                    // Renaming
-                    let a = self.bodies.get(&earliest_collision_body_a_id.unwrap());
-                    let b = self.bodies.get(&earliest_collision_body_b_id.unwrap());
+    // Both points pushing
+    let det = k11 * k22 - k12 * k12;
+    let x1 = (-k22 * vn1 + k12 * vn2) / det;
+    let x2 = (-k11 * vn2 + k12 * vn1) / det;
+    if x1 >= 0.0 && x2 >= 0.0 {
+        return vec![x1, x2];
+    }
 
+    // Only the first point pushing
+    let x1 = -vn1 / k11;
+    if x1 >= 0.0 && vn2 + k12 * x1 >= 0.0 {
+        return vec![x1, 0.0];
+    }
 
-                    // Decomposition
-                    a_velocity_into_plane, a_velocity_along_plane = a.velocity.decompose_into(earliest_collision_normal)
-                    b_velocity_into_plane, b_velocity_along_plane = b.velocity.decompose_into(earliest_collision_normal)
+    // Only the second point pushing
+    let x2 = -vn2 / k22;
+    if x2 >= 0.0 && vn1 + k12 * x2 >= 0.0 {
+        return vec![0.0, x2];
+    }
 
-                    // Velocity into plane
-                    a_mass_ratio = get_ratio(a.mass, b.mass)
-                    new_velocity_into_plane = a_velocity_into_plane * a_mass_ratio + b_velocity_into_plane * (1.0 - a_mass_ratio)
+    // Separating
+    return vec![0.0, 0.0];
+}
 
-                    // Velocity along plane (friction)
-                    velocity_keep = a.frictionless || b.frictionless ? 1.0 : (1.0 - a.friction) * (1.0 - b.friction)
-                    a_new_velocity_along_plane = a_velocity_along_plane * velocity_keep
-                    b_new_velocity_along_plane = b_velocity_along_plane * velocity_keep
+// Whether the two contact points can be solved together
+fn well_conditioned(a: &Body, b: &Body, normal: Vector, contacts: &[(Point, Point)]) -> bool {
+    let k11 = inverse_effective_mass(a, b, contacts[0], contacts[0], normal);
+    let k22 = inverse_effective_mass(a, b, contacts[1], contacts[1], normal);
+    let k12 = inverse_effective_mass(a, b, contacts[0], contacts[1], normal);
+    return k11 > 0.0 && k22 > 0.0 && k11 * k22 - k12 * k12 > 1e-6 * k11 * k22;
+}
 
+fn middle(contacts: &[(Point, Point)]) -> (Point, Point) {
+    let len = contacts.len() as f64;
+    let pa = contacts.iter().fold(Point::zero(), |sum, c| sum + c.0) / len;
+    let pb = contacts.iter().fold(Point::zero(), |sum, c| sum + c.1) / len;
+    return (pa, pb);
+}
 
-                    // Apply velocity
-                    a.velocity = a_new_velocity_along_plane + new_velocity_into_plane
-                    b.velocity = b_new_velocity_along_plane + new_velocity_into_plane
+// Change in the relative velocity along `direction` at the first contact
+// point, caused by a unit impulse along `direction` at the second one
+fn inverse_effective_mass(
+    a: &Body,
+    b: &Body,
+    first: (Point, Point),
+    second: (Point, Point),
+    direction: Vector,
+) -> f64 {
+    let (ca, cb) = (a.center(), b.center());
+    return 1.0 / a.mass
+        + 1.0 / b.mass
+        + ((first.0 - ca) ^ direction) * ((second.0 - ca) ^ direction) / a.inertia
+        + ((first.1 - cb) ^ direction) * ((second.1 - cb) ^ direction) / b.inertia;
+}
 
+// Share of the resulting velocity that corresponds to a body of mass `a`
+// when it collides with a body of mass `b`
+fn get_ratio(a: f64, b: f64) -> f64 {
+    if a == b {
+        return 0.5;
+    } else if a == f64::INFINITY {
+        return 1.0;
+    } else if b == f64::INFINITY {
+        return 0.0;
+    } else {
+        return a / (a + b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::square;
+    use crate::physics::broadphase::BruteForce;
+    use crate::physics::broadphase::SpatialGrid;
+    use crate::physics::broadphase::SweepAndPrune;
+    use crate::Capsule;
+    use crate::Circle;
+    use crate::Point;
+    use crate::RoundedPolygon;
+    use similar::assert_similar;
+    use similar::Similar;
+
+    #[test]
+    fn get_ratio_masses() {
+        assert_similar!(get_ratio(1.0, 3.0), 0.25);
+        assert_similar!(get_ratio(2.0, 2.0), 0.5);
+        assert_similar!(get_ratio(f64::INFINITY, 2.0), 1.0);
+        assert_similar!(get_ratio(2.0, f64::INFINITY), 0.0);
+        assert_similar!(get_ratio(f64::INFINITY, f64::INFINITY), 0.5);
+    }
+
+    #[test]
+    fn falling_body_lands_on_floor() {
+        let mut world = World::new();
+        world.add_static_body(square(-10.0, -20.0, 20.0), 0.0);
+        let id = world.add_body(square(0.0, 5.0, 1.0), 1.0, 0.0, false, false);
+
+        for _ in 0..100 {
+            world.update(0.1);
+        }
+
+        let body = world.body(id).unwrap();
+        assert_similar!(body.bottom(), 0.0);
+        assert_similar!(body.velocity, Vector::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn falling_body_slides_with_friction() {
+        let mut world = World::new();
+        world.add_static_body(square(-10.0, -20.0, 20.0), 0.5);
+        let id = world.add_body(square(0.0, 0.1, 1.0), 1.0, 0.0, false, false);
+        world.body(id).unwrap().velocity = Vector::new(4.0, 0.0);
+
+        world.update(0.1);
+
+        let body = world.body(id).unwrap();
+        assert_similar!(body.bottom(), 0.0);
+        assert_similar!(body.velocity, Vector::new(2.0, 0.0));
+    }
+
+    #[test]
+    fn equal_masses_share_velocity() {
+        let mut world = World::new();
+        let a = world.add_body(square(0.0, 0.0, 1.0), 1.0, 0.0, true, true);
+        let b = world.add_body(square(2.0, 0.0, 1.0), 1.0, 0.0, true, true);
+        world.body(a).unwrap().velocity = Vector::new(2.0, 0.0);
+
+        world.update(1.0);
+
+        assert_similar!(world.body(a).unwrap().velocity, Vector::new(1.0, 0.0));
+        assert_similar!(world.body(b).unwrap().velocity, Vector::new(1.0, 0.0));
+        assert_similar!(world.body(b).unwrap().left(), 2.5);
+    }
+
+    #[test]
+    fn separate_bodies_static() {
+        let mut world = World::new();
+        let floor = world.add_static_body(square(-10.0, -20.0, 20.0), 0.0);
+        let id = world.add_body(square(0.0, -0.5, 1.0), 1.0, 0.0, false, false);
+
+        assert!(world.separate_bodies());
+
+        assert_similar!(world.body(id).unwrap().bottom(), 0.0);
+        assert_similar!(world.body(floor).unwrap().top(), 0.0);
+    }
+
+    #[test]
+    fn separate_bodies_mass_ratio() {
+        let mut world = World::new();
+        let a = world.add_body(square(0.0, 0.0, 1.0), 3.0, 0.0, false, false);
+        let b = world.add_body(square(0.0, 0.6, 1.0), 1.0, 0.0, false, false);
+
+        assert!(world.separate_bodies());
+
+        assert_similar!(world.body(a).unwrap().bottom(), -0.1);
+        assert_similar!(world.body(b).unwrap().bottom(), 0.9);
+    }
+
+    #[test]
+    fn separate_bodies_off() {
+        let mut world = World::new();
+        world.separation = Separation::Off;
+        let a = world.add_body(square(0.0, 0.0, 1.0), 1.0, 0.0, false, false);
+        let b = world.add_body(square(0.0, 0.5, 1.0), 1.0, 0.0, false, false);
+
+        assert!(!world.separate_bodies());
+        assert_similar!(world.body(a).unwrap().bottom(), 0.0);
+
+        // Nothing to resolve
+        world.body(b).unwrap().move_y(2.0);
+        assert!(world.separate_bodies());
+    }
+
+    #[test]
+    fn separate_bodies_single_pass() {
+        let mut world = World::new();
+        world.separation = Separation::SinglePass;
+        world.add_body(square(0.0, 0.0, 1.0), 1.0, 0.0, false, false);
+        world.add_body(square(0.0, 0.5, 1.0), 1.0, 0.0, false, false);
+
+        // The only overlap is resolved by the single pass
+        assert!(world.separate_bodies());
+        assert!(world.separate_bodies());
+    }
+
+    #[test]
+    fn separate_bodies_last_pass_resolves() {
+        let mut world = World::new();
+        world.separation = Separation::Iterative { max_passes: 1 };
+        world.add_body(square(0.0, 0.0, 1.0), 1.0, 0.0, false, false);
+        world.add_body(square(0.0, 0.5, 1.0), 1.0, 0.0, false, false);
+
+        assert!(world.separate_bodies());
+    }
+
+    #[test]
+    fn separate_bodies_stack() {
+        let mut world = World::new();
+        world.separation = Separation::SinglePass;
+        world.add_static_body(square(-10.0, -20.0, 20.0), 0.0);
+        let ids: Vec<u32> = (0..3)
+            .map(|i| world.add_body(square(0.0, i as f64 * 0.8, 1.0), 1.0, 0.0, false, false))
+            .collect();
+
+        assert!(!world.separate_bodies());
+
+        world.separation = Separation::Iterative { max_passes: 100 };
+        assert!(world.separate_bodies());
+
+        for (i, id) in ids.iter().enumerate() {
+            assert_similar!(world.body(*id).unwrap().bottom(), i as f64, 1e-2);
+        }
+    }
+
+    #[test]
+    fn flat_landing_does_not_spin() {
+        let mut world = World::new();
+        world.add_static_body(square(-10.0, -20.0, 20.0), 0.0);
+        let id = world.add_body(square(0.0, 1.0, 1.0), 1.0, 0.0, false, false);
+
+        for _ in 0..10 {
+            world.update(0.1);
+        }
+
+        let body = world.body(id).unwrap();
+        assert_similar!(body.angular_velocity, 0.0);
+        assert_similar!(body.angle(), 0.0);
+    }
+
+    #[test]
+    fn off_center_landing_spins() {
+        let mut world = World::new();
+        world.add_static_body(square(-20.0, -20.0, 20.0), 0.0);
+        let id = world.add_body(square(-0.25, 1.0, 1.0), 1.0, 0.0, false, false);
+
+        world.update(0.5);
+
+        // Hanging off the right edge, so it tips clockwise
+        let body = world.body(id).unwrap();
+        assert!(body.angular_velocity < 0.0);
+        assert!(body.angle() < 0.0);
+    }
+
+    #[test]
+    fn bigger_bodies_are_heavier() {
+        let mut world = World::new();
+        let small = world.add_body_with_density(square(0.0, 0.0, 1.0), 1.0, 0.0, true, true);
+        let big = world.add_body_with_density(square(2.0, -1.0, 3.0), 1.0, 0.0, true, true);
+        world.body(small).unwrap().velocity = Vector::new(10.0, 0.0);
+
+        world.update(1.0);
+
+        // Momentum is shared as 1 * 10 = (1 + 9) * v
+        assert_similar!(world.body(big).unwrap().velocity, Vector::new(1.0, 0.0));
+        assert_similar!(world.body(small).unwrap().velocity, Vector::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn concave_terrain() {
+        let mut world = World::new();
+        let valley = Polygon::new(&vec![
+            Point::new(-10.0, -5.0),
+            Point::new(10.0, -5.0),
+            Point::new(10.0, 5.0),
+            Point::new(2.0, 0.0),
+            Point::new(-2.0, 0.0),
+            Point::new(-10.0, 5.0),
+        ]);
+        let ids = world.add_concave_static_body(&valley, 0.0);
+        assert!(ids.len() > 1);
+        let id = world.add_body(square(-0.5, 2.0, 1.0), 1.0, 0.0, false, false);
+
+        for _ in 0..10 {
+            world.update(0.1);
+        }
+
+        assert_similar!(world.body(id).unwrap().bottom(), 0.0);
+    }
+
+    #[test]
+    fn ball_lands_on_floor() {
+        let mut world = World::new();
+        world.add_static_body(square(-10.0, -20.0, 20.0), 0.0);
+        let id = world.add_body(
+            Circle::new(Point::new(0.0, 5.0), 0.5),
+            1.0,
+            0.0,
+            false,
+            false,
+        );
+
+        for _ in 0..100 {
+            world.update(0.1);
+        }
+
+        let body = world.body(id).unwrap();
+        assert_similar!(body.bottom(), 0.0);
+        assert_similar!(body.velocity, Vector::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn balls_collide() {
+        let mut world = World::new();
+        let a = world.add_body(Circle::new(Point::new(0.0, 0.0), 0.5), 1.0, 0.0, true, true);
+        let b = world.add_body(Circle::new(Point::new(2.0, 0.0), 0.5), 1.0, 0.0, true, true);
+        world.body(a).unwrap().velocity = Vector::new(2.0, 0.0);
+
+        world.update(1.0);
+
+        assert_similar!(world.body(a).unwrap().velocity, Vector::new(1.0, 0.0));
+        assert_similar!(world.body(b).unwrap().velocity, Vector::new(1.0, 0.0));
+        assert_similar!(world.body(a).unwrap().angular_velocity, 0.0);
+    }
+
+    #[test]
+    fn ball_rolls_off_a_ledge() {
+        let mut world = World::new();
+        world.add_static_body(square(-10.0, -20.0, 20.0), 0.0);
+        world.add_static_body(square(0.0, 0.0, 1.0), 0.0);
+        let ball = world.add_body(
+            Circle::new(Point::new(1.2, 2.0), 0.5),
+            1.0,
+            0.0,
+            false,
+            false,
+        );
+
+        for _ in 0..30 {
+            world.update(0.1);
+        }
+
+        // Pushed off the corner to the right, then resting on the floor
+        let ball = world.body(ball).unwrap();
+        assert!(ball.center().x > 1.5);
+        assert_similar!(ball.bottom(), 0.0);
+    }
+
+    #[test]
+    fn separate_ball_from_floor() {
+        let mut world = World::new();
+        world.add_static_body(square(-10.0, -20.0, 20.0), 0.0);
+        let id = world.add_body(
+            Circle::new(Point::new(0.0, 0.3), 0.5),
+            1.0,
+            0.0,
+            false,
+            false,
+        );
+
+        assert!(world.separate_bodies());
+
+        assert_similar!(world.body(id).unwrap().bottom(), 0.0);
+    }
+
+    #[test]
+    fn capsule_slides_over_tile_seams() {
+        let mut world = World::new();
+        for i in 0..4 {
+            world.add_static_body(square(i as f64 - 1.0, -1.0, 1.0), 0.0);
+        }
+        let segment = Segment::from_points(Point::new(-0.5, 0.25), Point::new(0.0, 0.25));
+        let id = world.add_body(Capsule::new(segment, 0.25), 1.0, 0.0, true, false);
+        world.body(id).unwrap().velocity = Vector::new(2.0, 0.0);
+
+        for _ in 0..10 {
+            world.update(0.1);
+        }
+
+        let body = world.body(id).unwrap();
+        assert_similar!(body.velocity.x, 2.0);
+        assert_similar!(body.bottom(), 0.0, 1e-6);
+        assert_similar!(body.left(), 1.25);
+    }
+
+    #[test]
+    fn sliding_over_tile_seams_adds_no_energy() {
+        let kinetic_energy = |body: &Body| {
+            0.5 * body.mass * (body.velocity * body.velocity)
+                + 0.5 * body.inertia * body.angular_velocity.powi(2)
+        };
+        for friction in [0.0, 0.2] {
+            let mut world = World::new();
+            for i in 0..12 {
+                world.add_static_body(square(i as f64 - 1.0, -1.0, 1.0), friction);
+            }
+            let segment = Segment::from_points(Point::new(-0.5, 0.25), Point::new(0.0, 0.25));
+            let capsule = world.add_body(Capsule::new(segment, 0.25), 1.0, friction, false, false);
+            let boxed = world.add_body(square(4.7, 0.0, 0.6), 1.0, friction, false, false);
+            world.body(capsule).unwrap().velocity = Vector::new(2.0, 0.0);
+            world.body(boxed).unwrap().velocity = Vector::new(1.0, 0.0);
+
+            // Resting bodies lose the speed gravity gives them, so only
+            // sliding and spinning are left
+            let ids = [capsule, boxed];
+            let mut energy: Vec<f64> = ids
+                .iter()
+                .map(|id| kinetic_energy(&world.bodies[id]))
+                .collect();
+            for _ in 0..20 {
+                world.update(0.1);
+                for (i, id) in ids.iter().enumerate() {
+                    let current = kinetic_energy(&world.bodies[id]);
+                    assert!(current <= energy[i] + 1e-9, "{} > {}", current, energy[i]);
+                    energy[i] = current;
                 }
             }
-            */
+        }
+    }
 
-            // Advance cycle
-            //time_left -= elapsed_time
-            //tries-= 1
+    #[test]
+    fn rounded_box_slides_over_tile_seams() {
+        let mut world = World::new();
+        for i in 0..4 {
+            world.add_static_body(square(i as f64 - 1.0, -1.0, 1.0), 0.0);
         }
+        let skin = 0.01;
+        let core = square(-0.5 + skin, skin, 0.5 - 2.0 * skin);
+        let id = world.add_body(RoundedPolygon::new(core, skin), 1.0, 0.0, true, false);
+        world.body(id).unwrap().velocity = Vector::new(2.0, 0.0);
 
-        /*
-        // If there's still time left, we ignore collisions and just attempt to finish the update
-        @bodies.each do |body|
-          body.move(body.velocity * time_left)
-        end
-        separate_bodies()
-        */
-    }
-}
-
-/*
-
-    private
-
-    def separate_bodies()
-      each_collidable_bodies do |a, b|
-        if Nulu::Collision.colliding?(a.shape, b.shape)
-          mtv = Nulu::Collision.mtv(a.shape, b.shape)
-          a_mass_ratio = get_ratio(a.mass, b.mass)
-          b.move(mtv * a_mass_ratio)
-          a.move(-mtv * (1.0 - a_mass_ratio))
-        end
-      end
-    end
-
-    def each_collidable_bodies()
-      @collision_enabler.each_collidable_pair do |a, b|
-        yield(a, b) unless @collision_skip.include?(SortedPair.new(a.id, b.id))
-      end
-    end
-
-    def get_ratio(a, b)
-      if a == b
-        return 0.5
-      elsif a == INF
-        return 1.0
-      elsif b == INF
-        return 0.0
-      else
-        return a / (a + b)
-      end
-    end
-
-    class SortedPair
-      attr_reader :min, :max
-      def initialize(a, b) @min, @max = [a,b].min, [a,b].max end
-      def eql?(other) min.eql?(other.min) && max.eql?(other.max) end
-      def hash() [min, max].hash end
-    end
-
-  end
-end
-
-*/
+        for _ in 0..10 {
+            world.update(0.1);
+        }
+
+        let body = world.body(id).unwrap();
+        assert_similar!(body.velocity.x, 2.0);
+        assert_similar!(body.angle(), 0.0);
+        assert_similar!(body.bottom(), 0.0, 1e-6);
+        assert_similar!(body.left(), 1.5);
+    }
+
+    #[test]
+    fn box_slides_along_chain() {
+        let mut world = World::new();
+        let ground: Vec<Point> = (0..8).map(|i| Point::new(4.0 - i as f64, 0.0)).collect();
+        let ids = world.add_chain(&Chain::new(&ground, true), 0.0);
+        assert_eq!(ids.len(), 7);
+        let id = world.add_body(square(-3.0, 0.0, 1.0), 1.0, 0.0, true, false);
+        world.body(id).unwrap().velocity = Vector::new(2.0, 0.0);
+
+        for _ in 0..10 {
+            world.update(0.1);
+        }
+
+        let body = world.body(id).unwrap();
+        assert_similar!(body.velocity.x, 2.0);
+        assert_similar!(body.angle(), 0.0);
+        assert_similar!(body.bottom(), 0.0, 1e-6);
+        assert_similar!(body.left(), -1.0);
+    }
+
+    #[test]
+    fn one_sided_chain_lets_bodies_jump_through() {
+        let mut world = World::new();
+        let platform = Chain::new(&[Point::new(1.0, 0.0), Point::new(-1.0, 0.0)], true);
+        world.add_chain(&platform, 0.0);
+        let id = world.add_body(square(-0.5, -1.5, 1.0), 1.0, 0.0, false, true);
+        world.body(id).unwrap().velocity = Vector::new(0.0, 2.0);
+
+        world.update(1.0);
+
+        // Went through from below, and stopped by it when falling back
+        let body = world.body(id).unwrap();
+        assert_similar!(body.bottom(), 0.5);
+        world.body(id).unwrap().gravityless = false;
+        world.body(id).unwrap().velocity = Vector::zero();
+        for _ in 0..10 {
+            world.update(0.1);
+        }
+        assert_similar!(world.body(id).unwrap().bottom(), 0.0);
+    }
+
+    #[test]
+    fn broadphase_only_tests_nearby_pairs() {
+        let mut world = World::new();
+        world.add_static_body(square(-100.0, -200.0, 200.0), 0.0);
+        let ids: Vec<u32> = (0..50)
+            .map(|i| {
+                world.add_body(
+                    square(i as f64 * 3.0 - 75.0, 0.5, 1.0),
+                    1.0,
+                    0.0,
+                    true,
+                    false,
+                )
+            })
+            .collect();
+
+        world.update(0.5);
+
+        // Each box only ever meets the floor
+        assert!(world.stats.sub_steps > 1);
+        assert!(world.stats.pairs_tested <= 50 * world.stats.sub_steps as usize);
+        assert_eq!(world.stats.collisions, 50);
+        for id in ids {
+            assert_similar!(world.body(id).unwrap().bottom(), 0.0);
+        }
+    }
+
+    #[test]
+    fn removed_bodies_leave_the_broadphase() {
+        let mut world = World::new();
+        let floor = world.add_static_body(square(-5.0, -1.0, 10.0), 0.0);
+        let id = world.add_body(square(0.0, 0.5, 1.0), 1.0, 0.0, true, false);
+        world.update(0.1);
+        world.bodies.remove(&floor);
+        world.update(0.5);
+        assert_eq!(world.stats.candidate_pairs, 0);
+        assert!(world.body(id).unwrap().bottom() < -0.5);
+    }
+
+    #[test]
+    fn every_broadphase_gives_the_same_result() {
+        let scene = |mut world: World| {
+            world.add_static_body(square(-50.0, -100.0, 100.0), 0.0);
+            let ids: Vec<u32> = (0..10)
+                .map(|i| {
+                    let x = i as f64 * 2.0 - 10.0;
+                    let id = world.add_body(square(x, 0.0, 1.0), 1.0, 0.0, false, false);
+                    let direction = if i % 2 == 0 { 1.0 } else { -1.0 };
+                    world.body(id).unwrap().velocity = Vector::new(direction, 0.0);
+                    return id;
+                })
+                .collect();
+            for _ in 0..10 {
+                world.update(0.1);
+            }
+            return ids
+                .iter()
+                .map(|id| world.body(*id).unwrap().center())
+                .collect::<Vec<Point>>();
+        };
+
+        let expected = scene(World::with_broadphase(BruteForce::new()));
+        let results = vec![
+            scene(World::new()),
+            scene(World::with_broadphase(SweepAndPrune::new())),
+            scene(World::with_broadphase(SpatialGrid::new(2.0))),
+        ];
+        for result in results {
+            for (center, expected) in result.iter().zip(expected.iter()) {
+                assert_similar!(*center, *expected);
+            }
+        }
+    }
+
+    #[test]
+    fn raycast_closest_any_and_all() {
+        let mut world = World::new();
+        let near = world.add_static_body(square(2.0, -1.0, 2.0), 0.0);
+        let ball = world.add_body(
+            Circle::new(Point::new(7.0, 0.0), 1.0),
+            1.0,
+            0.0,
+            false,
+            true,
+        );
+        let far = world.add_static_body(square(10.0, -1.0, 2.0), 0.0);
+        world.add_static_body(square(2.0, 5.0, 2.0), 0.0);
+        let ray = Ray::new(Point::zero(), Vector::new(1.0, 0.0));
+
+        let (id, hit) = world.raycast(&ray, 20.0, |_| true).unwrap();
+        assert_eq!(id, near);
+        assert_similar!(hit.point, Point::new(2.0, 0.0));
+        assert_similar!(hit.normal, Vector::new(-1.0, 0.0));
+
+        let (id, hit) = world.raycast(&ray, 20.0, |id| id != near).unwrap();
+        assert_eq!(id, ball);
+        assert_similar!(hit.distance, 6.0);
+
+        let all: Vec<u32> = world
+            .raycast_all(&ray, 20.0, |_| true)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(all, vec![near, ball, far]);
+        assert_eq!(world.raycast_all(&ray, 7.0, |_| true).len(), 2);
+
+        assert!(world.raycast_any(&ray, 20.0, |_| true).is_some());
+        assert!(world.raycast_any(&ray, 1.0, |_| true).is_none());
+        let up = Ray::new(Point::zero(), Vector::new(0.0, 1.0));
+        assert!(world.raycast_any(&up, 20.0, |_| true).is_none());
+
+        // Ties go to the lowest id
+        let mut world = World::new();
+        let below = world.add_static_body(square(2.0, -2.0, 2.0), 0.0);
+        world.add_static_body(square(2.0, 0.0, 2.0), 0.0);
+        assert_eq!(world.raycast_all(&ray, 20.0, |_| true).len(), 2);
+        let (id, hit) = world.raycast(&ray, 20.0, |_| true).unwrap();
+        assert_eq!(id, below);
+        assert_similar!(hit.distance, 2.0);
+    }
+
+    #[test]
+    fn raycast_sees_bodies_added_since_the_last_update() {
+        let mut world = World::new();
+        world.update(0.1);
+        let id = world.add_static_body(square(-1.0, -3.0, 2.0), 0.0);
+        let down = Ray::new(Point::zero(), Vector::new(0.0, -1.0));
+        let (hit_id, hit) = world.raycast(&down, 10.0, |_| true).unwrap();
+        assert_eq!(hit_id, id);
+        assert_similar!(hit.distance, 1.0);
+    }
+
+    #[test]
+    fn shape_cast() {
+        let mut world = World::new();
+        let floor = world.add_static_body(square(-5.0, -10.0, 10.0), 0.0);
+        let wall = world.add_static_body(square(3.0, 0.0, 1.0), 0.0);
+
+        // Falling diagonally onto the floor
+        let probe = square(-0.5, 2.0, 1.0);
+        let hit = world
+            .shape_cast(&probe, Vector::new(1.0, -4.0), |_| true)
+            .unwrap();
+        assert_eq!(hit.id, floor);
+        assert_similar!(hit.time, 0.5);
+        assert_similar!(hit.normal, Vector::new(0.0, 1.0));
+
+        // Sliding along the floor into the wall, but not when moving away
+        let resting = square(0.0, 0.0, 1.0);
+        let hit = world
+            .shape_cast(&resting, Vector::new(4.0, 0.0), |_| true)
+            .unwrap();
+        assert_eq!(hit.id, wall);
+        assert_similar!(hit.time, 0.5);
+        assert_similar!(hit.normal, Vector::new(-1.0, 0.0));
+        assert!(world
+            .shape_cast(&resting, Vector::new(-4.0, 0.0), |_| true)
+            .is_none());
+        assert!(world
+            .shape_cast(&resting, Vector::new(4.0, 0.0), |id| id != wall)
+            .is_none());
+
+        // Too short to reach anything, and the world is left untouched
+        assert!(world
+            .shape_cast(&probe, Vector::new(0.0, -0.5), |_| true)
+            .is_none());
+        assert_similar!(world.body(wall).unwrap().left(), 3.0);
+    }
+
+    #[test]
+    fn shape_cast_sees_added_and_removed_bodies() {
+        let mut world = World::new();
+        let floor = world.add_static_body(square(-5.0, -10.0, 10.0), 0.0);
+        let id = world.add_body(square(-1.0, 0.0, 2.0), 1.0, 0.0, false, true);
+        let cut = Segment::from_points(Point::new(0.0, -5.0), Point::new(0.0, 5.0));
+        let (_, right) = world.slice_body(id, cut).unwrap();
+        world.bodies.remove(&floor);
+
+        // Queries don't need a mutable world
+        let world = &world;
+        let hit = world
+            .shape_cast(&square(0.25, 3.0, 0.5), Vector::new(0.0, -4.0), |_| true)
+            .unwrap();
+        assert_eq!(hit.id, right);
+        assert_similar!(hit.time, 0.25);
+        assert!(world
+            .shape_cast(&square(2.0, 3.0, 0.5), Vector::new(0.0, -4.0), |_| true)
+            .is_none());
+    }
+
+    #[test]
+    fn slice_body() {
+        let mut world = World::new();
+        let crate_id = world.add_body_with_density(square(0.0, 0.0, 2.0), 3.0, 0.0, false, true);
+        let body = world.body(crate_id).unwrap();
+        body.velocity = Vector::new(1.0, 0.0);
+        body.angular_velocity = 0.5;
+
+        let cut = Segment::from_points(Point::new(0.5, -1.0), Point::new(0.5, 3.0));
+        let (left_id, right_id) = world.slice_body(crate_id, cut).unwrap();
+        assert!(world.body(crate_id).is_none());
+
+        let left = world.body(left_id).unwrap();
+        assert_similar!(left.mass, 3.0);
+        assert_similar!(left.inertia, left.shape().moment_of_inertia(3.0));
+        assert_similar!(left.center(), Point::new(0.25, 1.0));
+        // Moving like that part of the crate, which spins around (1, 1)
+        assert_similar!(left.velocity, Vector::new(1.0, -0.375));
+        assert_similar!(left.angular_velocity, 0.5);
+
+        let right = world.body(right_id).unwrap();
+        assert_similar!(right.mass, 9.0);
+        assert_similar!(right.velocity, Vector::new(1.0, 0.125));
+
+        // Nothing to cut
+        let miss = Segment::from_points(Point::new(5.0, -1.0), Point::new(5.0, 3.0));
+        assert_eq!(world.slice_body(right_id, miss), None);
+        let ball = world.add_body(Circle::new(Point::zero(), 1.0), 1.0, 0.0, false, true);
+        assert_eq!(world.slice_body(ball, cut), None);
+        assert_eq!(world.bodies.len(), 3);
+    }
+}
@@ -29,6 +29,25 @@ where
     }
 }
 
+impl<A, B> Similar for (A, B)
+where
+    A: Similar,
+    B: Similar,
+{
+    fn is_similar(self, other: (A, B), eps: f64) -> bool {
+        return self.0.is_similar(other.0, eps) && self.1.is_similar(other.1, eps);
+    }
+}
+
+impl<T, const N: usize> Similar for [T; N]
+where
+    T: Similar + Copy,
+{
+    fn is_similar(self, other: [T; N], eps: f64) -> bool {
+        return self[..].is_similar(&other[..], eps);
+    }
+}
+
 /* NOTE:
 The generic implementetion for IntoIterator trait produces conflicts with the
 implementations for f64 and u32. AFAIU that's because somewhere and somewhen